@@ -0,0 +1,246 @@
+use std::f64::consts::PI;
+
+use crate::dataset::{Dataset, Loss, Supervised};
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::population::Population;
+
+//first ten samples of every class of Fisher's iris data:
+//sepal length, sepal width, petal length, petal width, class
+const IRIS: &str = "
+5.1,3.5,1.4,0.2,0
+4.9,3.0,1.4,0.2,0
+4.7,3.2,1.3,0.2,0
+4.6,3.1,1.5,0.2,0
+5.0,3.6,1.4,0.2,0
+5.4,3.9,1.7,0.4,0
+4.6,3.4,1.4,0.3,0
+5.0,3.4,1.5,0.2,0
+4.4,2.9,1.4,0.2,0
+4.9,3.1,1.5,0.1,0
+7.0,3.2,4.7,1.4,1
+6.4,3.2,4.5,1.5,1
+6.9,3.1,4.9,1.5,1
+5.5,2.3,4.0,1.3,1
+6.5,2.8,4.6,1.5,1
+5.7,2.8,4.5,1.3,1
+6.3,3.3,4.7,1.6,1
+4.9,2.4,3.3,1.0,1
+6.6,2.9,4.6,1.3,1
+5.2,2.7,3.9,1.4,1
+6.3,3.3,6.0,2.5,2
+5.8,2.7,5.1,1.9,2
+7.1,3.0,5.9,2.1,2
+6.3,2.9,5.6,1.8,2
+6.5,3.0,5.8,2.2,2
+7.6,3.0,6.6,2.1,2
+4.9,2.5,4.5,1.7,2
+7.3,2.9,6.3,1.8,2
+6.7,2.5,5.8,1.8,2
+7.2,3.6,6.1,2.5,2
+";
+
+pub struct Benchmark {
+    pub name: String,
+    //every row is used for training, the loss is turned into fitness while evolving
+    pub task: Supervised,
+    //measure and value the champion has to reach to solve the task,
+    //at least the target for accuracy and at most the target for errors
+    pub success: Loss,
+    pub target: f64,
+}
+
+impl Benchmark {
+    pub fn inputs(&self) -> i32 {
+        self.task.train.inputs[0].len() as i32
+    }
+
+    pub fn outputs(&self) -> i32 {
+        self.task.train.targets[0].len() as i32
+    }
+
+    pub fn population(&self, size: i32, act: fn(f64) -> f64) -> Population {
+        Population::new(size, self.inputs(), self.outputs(), act, true)
+    }
+
+    pub fn fitness(&self, genome: &Genome) -> f64 {
+        self.task.fitness(genome)
+    }
+
+    pub fn try_fitness(&self, genome: &Genome) -> Result<f64, NeatError> {
+        self.task.try_fitness(genome)
+    }
+
+    pub fn evaluate_population(&self, population: &Population) -> Vec<f64> {
+        self.task.evaluate_population(population)
+    }
+
+    pub fn solved(&self, genome: &Genome) -> bool {
+        let score = self.success.score(genome, &self.task.train);
+        match self.success {
+            Loss::Accuracy => score >= self.target,
+            _ => score <= self.target,
+        }
+    }
+}
+
+fn bits(value: usize, count: usize) -> Vec<f64> {
+    (0..count).map(|i| ((value >> i) & 1) as f64).collect()
+}
+
+fn boolean(name: String, inputs: Vec<Vec<f64>>, targets: Vec<Vec<f64>>) -> Benchmark {
+    //boolean functions are solved once every row lands on the right side of 0.5
+    Benchmark {
+        name: name,
+        task: Supervised::without_validation(Dataset::new(inputs, targets), Loss::MeanSquared),
+        success: Loss::Accuracy,
+        target: 1.0,
+    }
+}
+
+pub fn xor() -> Benchmark {
+    let mut b = parity(2);
+    b.name = "xor".to_string();
+    b
+}
+
+pub fn parity(n: usize) -> Benchmark {
+    //1 when an odd number of the n inputs are set
+    let inputs: Vec<Vec<f64>> = (0..1 << n).map(|v| bits(v, n)).collect();
+    let targets: Vec<Vec<f64>> = (0..1 << n)
+        .map(|v: usize| vec![(v.count_ones() % 2) as f64])
+        .collect();
+    boolean(format!("parity{}", n), inputs, targets)
+}
+
+pub fn multiplexer(address: usize) -> Benchmark {
+    //the first address bits select which of the following data bits is the output,
+    //2 address bits give the 6-multiplexer and 3 the 11-multiplexer
+    let n = address + (1 << address);
+    let mut inputs: Vec<Vec<f64>> = vec![];
+    let mut targets: Vec<Vec<f64>> = vec![];
+    for v in 0..1 << n {
+        let row = bits(v, n);
+        let selected = (v & ((1 << address) - 1)) + address;
+        targets.push(vec![row[selected]]);
+        inputs.push(row);
+    }
+    boolean(format!("multiplexer{}", n), inputs, targets)
+}
+
+pub fn two_spirals() -> Benchmark {
+    //Lang and Witbrock's 97 points per spiral, solved when 90% of the points are classified
+    let mut inputs: Vec<Vec<f64>> = vec![];
+    let mut targets: Vec<Vec<f64>> = vec![];
+    for i in 0..97 {
+        let angle = i as f64 * PI / 16.0;
+        let radius = (104 - i) as f64 / 104.0;
+        let (x, y) = (radius * f64::sin(angle), radius * f64::cos(angle));
+        inputs.push(vec![x, y]);
+        targets.push(vec![1.0]);
+        inputs.push(vec![-x, -y]);
+        targets.push(vec![0.0]);
+    }
+    Benchmark {
+        name: "spirals".to_string(),
+        task: Supervised::without_validation(Dataset::new(inputs, targets), Loss::MeanSquared),
+        success: Loss::Accuracy,
+        target: 0.9,
+    }
+}
+
+fn regression(name: &str, xs: Vec<f64>, scale: f64, f: fn(f64) -> f64) -> Benchmark {
+    //fit f on the sampled points, solved at a mean squared error of 0.01
+    let inputs: Vec<Vec<f64>> = xs.iter().map(|x| vec![x / scale]).collect();
+    let targets: Vec<Vec<f64>> = xs.iter().map(|x| vec![f(*x)]).collect();
+    Benchmark {
+        name: name.to_string(),
+        task: Supervised::without_validation(Dataset::new(inputs, targets), Loss::MeanSquared),
+        success: Loss::MeanSquared,
+        target: 0.01,
+    }
+}
+
+fn check_samples(samples: usize) -> Result<(), NeatError> {
+    //the first and last sample sit on the ends of the interval
+    if samples < 2 {
+        return Err(NeatError::InvalidConfig(format!(
+            "regression needs at least 2 samples but got {}",
+            samples
+        )));
+    }
+    Ok(())
+}
+
+pub fn sine(samples: usize) -> Benchmark {
+    match try_sine(samples) {
+        Ok(b) => b,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+pub fn try_sine(samples: usize) -> Result<Benchmark, NeatError> {
+    //sin over one period shifted into [0, 1]
+    check_samples(samples)?;
+    let xs = (0..samples)
+        .map(|i| 2.0 * PI * i as f64 / (samples - 1) as f64)
+        .collect();
+    Ok(regression("sine", xs, 2.0 * PI, |x| {
+        (f64::sin(x) + 1.0) / 2.0
+    }))
+}
+
+pub fn polynomial(samples: usize) -> Benchmark {
+    match try_polynomial(samples) {
+        Ok(b) => b,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+pub fn try_polynomial(samples: usize) -> Result<Benchmark, NeatError> {
+    //x^3 - x/2 + 1/2 over [-1, 1]
+    check_samples(samples)?;
+    let xs = (0..samples)
+        .map(|i| -1.0 + 2.0 * i as f64 / (samples - 1) as f64)
+        .collect();
+    Ok(regression("polynomial", xs, 1.0, |x| {
+        x * x * x - x / 2.0 + 0.5
+    }))
+}
+
+pub fn iris() -> Benchmark {
+    //three classes with one output each, solved at 95% accuracy
+    let raw = Dataset::parse(IRIS, 1).unwrap();
+    let inputs = raw
+        .inputs
+        .iter()
+        .map(|r| r.iter().map(|v| v / 8.0).collect())
+        .collect();
+    let targets = raw
+        .targets
+        .iter()
+        .map(|t| (0..3).map(|c| (t[0] as usize == c) as i32 as f64).collect())
+        .collect();
+    Benchmark {
+        name: "iris".to_string(),
+        task: Supervised::without_validation(Dataset::new(inputs, targets), Loss::CrossEntropy),
+        success: Loss::Accuracy,
+        target: 0.95,
+    }
+}
+
+pub fn by_name(name: &str) -> Option<Benchmark> {
+    //Names used by the harness and command line
+    match name {
+        "xor" => Some(xor()),
+        "parity3" => Some(parity(3)),
+        "parity4" => Some(parity(4)),
+        "multiplexer6" => Some(multiplexer(2)),
+        "multiplexer11" => Some(multiplexer(3)),
+        "spirals" => Some(two_spirals()),
+        "sine" => Some(sine(20)),
+        "polynomial" => Some(polynomial(20)),
+        "iris" => Some(iris()),
+        _ => None,
+    }
+}
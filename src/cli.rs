@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::thread;
+
+use crate::activation::{sigmoid, tanh};
+use crate::benchmarks::{by_name, Benchmark};
+use crate::config::Config;
+use crate::dataset::{Dataset, Loss};
+use crate::distributed::{run_worker, Coordinator};
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::helper::seed;
+use crate::population::Population;
+
+pub const USAGE: &str = "usage:
+    neat run <benchmark> [--config FILE] [--seed N] [--generations N] [--output DIR] [--threads N]
+        [--listen ADDR --workers N]
+    neat resume <checkpoint> [--config FILE] [--seed N] [--generations N] [--output DIR] [--threads N]
+        [--listen ADDR --workers N]
+    neat evaluate <genome> <csv> [--loss mse|mae|cross-entropy|accuracy] [--activation NAME]
+    neat dot <genome> [--output FILE]
+    neat worker <addr> <benchmark> [--activation NAME]
+benchmarks: xor parity3 parity4 multiplexer6 multiplexer11 spirals sine polynomial iris
+config files hold key = value lines with population, activation and any Config field
+with --listen fitness is computed by N workers started with the same benchmark and activation";
+
+pub struct Options {
+    pub positional: Vec<String>,
+    pub flags: BTreeMap<String, String>,
+}
+
+impl Options {
+    pub fn parse(args: &[String]) -> Result<Self, NeatError> {
+        //Positional arguments and --flag value pairs in any order
+        let mut options = Options {
+            positional: vec![],
+            flags: BTreeMap::new(),
+        };
+        let mut args = args.iter();
+        while let Some(a) = args.next() {
+            match a.strip_prefix("--") {
+                Some(flag) => {
+                    let v = args
+                        .next()
+                        .ok_or(NeatError::Parse(format!("missing value for --{}", flag)))?;
+                    options.flags.insert(flag.to_string(), v.clone());
+                }
+                None => options.positional.push(a.clone()),
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn check_flags(&self, accepted: &[&str]) -> Result<(), NeatError> {
+        //flags the subcommand doesn't know are most likely typos so they aren't ignored
+        match self.flags.keys().find(|f| !accepted.contains(&f.as_str())) {
+            Some(flag) => Err(NeatError::Parse(format!(
+                "unknown flag --{}\n{}",
+                flag, USAGE
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    pub fn arg(&self, idx: usize) -> Result<&str, NeatError> {
+        self.positional
+            .get(idx)
+            .map(|s| s.as_str())
+            .ok_or(NeatError::Parse(USAGE.to_string()))
+    }
+
+    pub fn flag<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, NeatError> {
+        match self.flags.get(name) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| NeatError::Parse(format!("invalid value {} for --{}", v, name))),
+            None => Ok(default),
+        }
+    }
+}
+
+pub fn activation(name: &str) -> Result<fn(f64) -> f64, NeatError> {
+    match name {
+        "sigmoid" => Ok(sigmoid),
+        "tanh" => Ok(tanh),
+        _ => Err(NeatError::Parse(format!("unknown activation {}", name))),
+    }
+}
+
+pub fn loss(name: &str) -> Result<Loss, NeatError> {
+    match name {
+        "mse" => Ok(Loss::MeanSquared),
+        "mae" => Ok(Loss::MeanAbsolute),
+        "cross-entropy" => Ok(Loss::CrossEntropy),
+        "accuracy" => Ok(Loss::Accuracy),
+        _ => Err(NeatError::Parse(format!("unknown loss {}", name))),
+    }
+}
+
+fn read(path: &str) -> Result<String, NeatError> {
+    fs::read_to_string(path).map_err(|e| NeatError::Io(format!("{}: {}", path, e)))
+}
+
+fn write(path: &Path, text: &str) -> Result<(), NeatError> {
+    fs::write(path, text).map_err(|e| NeatError::Io(format!("{}: {}", path.display(), e)))
+}
+
+pub struct Experiment {
+    pub config: Config,
+    pub population: i32,
+    pub activation: String,
+}
+
+impl Experiment {
+    pub fn parse(text: &str) -> Result<Self, NeatError> {
+        //key = value lines, # starts a comment
+        let mut e = Experiment {
+            config: Config::default(),
+            population: 150,
+            activation: "sigmoid".to_string(),
+        };
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, v) = line.split_once('=').ok_or(NeatError::Parse(format!(
+                "expected key = value, found {}",
+                line
+            )))?;
+            let (key, v) = (key.trim(), v.trim());
+            match key {
+                "population" => {
+                    e.population = v
+                        .parse()
+                        .map_err(|_| NeatError::Parse(format!("invalid population {}", v)))?
+                }
+                "activation" => {
+                    activation(v)?;
+                    e.activation = v.to_string();
+                }
+                _ => e.config.set(key, v)?,
+            }
+        }
+        e.config.validate()?;
+        Ok(e)
+    }
+}
+
+pub fn evaluate_parallel(
+    task: &Benchmark,
+    pop: &Population,
+    threads: usize,
+) -> Result<Vec<f64>, NeatError> {
+    //Genomes share nodes through Rc so every thread rebuilds its share from text
+    if threads <= 1 {
+        return pop.population.iter().map(|g| task.try_fitness(g)).collect();
+    }
+    let texts: Vec<String> = pop.population.iter().map(|g| g.to_text()).collect();
+    let chunk = texts.len().div_ceil(threads).max(1);
+    let act = pop.act;
+    thread::scope(|s| {
+        let handles: Vec<_> = texts
+            .chunks(chunk)
+            .map(|part| {
+                s.spawn(move || -> Result<Vec<f64>, NeatError> {
+                    let mut fitness: Vec<f64> = vec![];
+                    for t in part {
+                        fitness.push(task.try_fitness(&Genome::from_text(t, act)?)?);
+                    }
+                    Ok(fitness)
+                })
+            })
+            .collect();
+        //join every thread before reporting the first error
+        let parts: Vec<_> = handles.into_iter().map(|h| h.join()).collect();
+        let mut fitness: Vec<f64> = vec![];
+        for part in parts {
+            let part = part
+                .map_err(|_| NeatError::InvalidGenome("evaluation thread panicked".to_string()))?;
+            fitness.extend(part?);
+        }
+        Ok(fitness)
+    })
+}
+
+fn evolve(
+    task: &Benchmark,
+    mut pop: Population,
+    activation: &str,
+    run_seed: u64,
+    options: &Options,
+) -> Result<(), NeatError> {
+    //Evolve until solved or out of generations, checkpointing every generation.
+    //Every generation reseeds from the run seed and the generation number so resuming
+    //the same checkpoint twice gives the same run, which still differs from the original
+    //run because species are rebuilt from the checkpoint
+    let generations: i32 = options.flag("generations", 100)?;
+    let threads: usize = options.flag("threads", 1)?;
+    let output: String = options.flag("output", "output".to_string())?;
+    let dir = Path::new(&output);
+    fs::create_dir_all(dir).map_err(|e| NeatError::Io(format!("{}: {}", output, e)))?;
+    let mut coordinator = match options.flags.get("listen") {
+        Some(addr) => {
+            let mut c = Coordinator::bind(addr.as_str())?;
+            println!("listening on {}", c.local_addr()?);
+            c.accept(options.flag("workers", 1)?)?;
+            Some(c)
+        }
+        None => None,
+    };
+    let last = pop.gen + generations;
+    loop {
+        seed(run_seed.wrapping_add(pop.gen as u64));
+        let mut fitness = match &mut coordinator {
+            Some(c) => c.evaluate(&pop.population)?,
+            None => evaluate_parallel(task, &pop, threads)?,
+        };
+        let best = fitness
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(idx, _)| idx)
+            .ok_or(NeatError::Parse("empty population".to_string()))?;
+        let champion = &pop.population[best];
+        let solved = task.solved(champion);
+        println!(
+            "generation {} best {} species {} nodes {} connections {}",
+            pop.gen,
+            fitness[best],
+            pop.previous_gen.len(),
+            champion.num_nodes,
+            champion.num_connections
+        );
+        write(&dir.join("champion.txt"), &champion.to_text())?;
+        write(&dir.join("champion.dot"), &champion.to_dot())?;
+        if solved || pop.gen >= last {
+            println!(
+                "{} after {} generations",
+                if solved { "solved" } else { "not solved" },
+                pop.gen
+            );
+            return Ok(());
+        }
+        pop.try_next_generation(&mut fitness)?;
+        let checkpoint = format!(
+            "task {}\nactivation {}\nseed {}\n{}",
+            task.name,
+            activation,
+            run_seed,
+            pop.checkpoint()
+        );
+        write(&dir.join("checkpoint.txt"), &checkpoint)?;
+    }
+}
+
+fn experiment(options: &Options) -> Result<Experiment, NeatError> {
+    match options.flags.get("config") {
+        Some(path) => Experiment::parse(&read(path)?),
+        None => Experiment::parse(""),
+    }
+}
+
+fn benchmark(name: &str) -> Result<Benchmark, NeatError> {
+    by_name(name).ok_or(NeatError::Parse(format!("unknown benchmark {}", name)))
+}
+
+pub fn run(args: &[String]) -> Result<(), NeatError> {
+    let options = Options::parse(args)?;
+    let accepted: &[&str] = match options.arg(0)? {
+        "run" | "resume" => &[
+            "config",
+            "seed",
+            "generations",
+            "output",
+            "threads",
+            "listen",
+            "workers",
+        ],
+        "evaluate" => &["loss", "activation"],
+        "dot" => &["output"],
+        "worker" => &["activation"],
+        _ => return Err(NeatError::Parse(USAGE.to_string())),
+    };
+    options.check_flags(accepted)?;
+    //runs without --seed pick one at random and report it so they can be repeated
+    let seed_flag: Option<u64> = match options.flags.get("seed") {
+        Some(_) => Some(options.flag("seed", 0)?),
+        None => None,
+    };
+    if let Some(s) = seed_flag {
+        seed(s);
+    }
+    match options.arg(0)? {
+        "run" => {
+            let task = benchmark(options.arg(1)?)?;
+            let e = experiment(&options)?;
+            let act = activation(&e.activation)?;
+            let pop = Population::with_config(
+                e.population,
+                task.inputs(),
+                task.outputs(),
+                act,
+                true,
+                e.config,
+            );
+            let run_seed = seed_flag.unwrap_or_else(rand::random);
+            println!("seed {}", run_seed);
+            evolve(&task, pop, &e.activation, run_seed, &options)
+        }
+        "resume" => {
+            //the checkpoint starts with the task, activation and seed it was written for,
+            //--seed overrides the stored seed
+            let text = read(options.arg(1)?)?;
+            let mut lines = text.splitn(4, '\n');
+            let mut header = |name: &str| -> Result<String, NeatError> {
+                lines
+                    .next()
+                    .and_then(|l| l.strip_prefix(name))
+                    .map(|v| v.trim().to_string())
+                    .ok_or(NeatError::Parse(format!("checkpoint is missing {}", name)))
+            };
+            let task = benchmark(&header("task")?)?;
+            let act_name = header("activation")?;
+            let stored = header("seed")?;
+            let stored: u64 = stored
+                .parse()
+                .map_err(|_| NeatError::Parse(format!("invalid seed {}", stored)))?;
+            let rest = lines.next().unwrap_or("");
+            let e = experiment(&options)?;
+            //speciating the loaded genomes already draws random numbers
+            let run_seed = seed_flag.unwrap_or(stored);
+            seed(run_seed);
+            println!("seed {}", run_seed);
+            let pop = Population::from_checkpoint(rest, activation(&act_name)?, e.config)?;
+            evolve(&task, pop, &act_name, run_seed, &options)
+        }
+        "evaluate" => {
+            let act = activation(&options.flag("activation", "sigmoid".to_string())?)?;
+            let genome = Genome::from_text(&read(options.arg(1)?)?, act)?;
+            let data = Dataset::load(options.arg(2)?, genome.output_nodes as usize)?;
+            let l = loss(&options.flag("loss", "mse".to_string())?)?;
+            //rows must have as many columns as the genome has inputs and outputs
+            if let Some(row) = data.inputs.first() {
+                if row.len() != genome.input_nodes as usize {
+                    return Err(NeatError::InputLength {
+                        expected: genome.input_nodes as usize,
+                        found: row.len(),
+                    });
+                }
+            }
+            println!("{}", l.try_score(&genome, &data)?);
+            Ok(())
+        }
+        "worker" => {
+            //evaluate genomes for a coordinator started with run or resume --listen
+            let task = benchmark(options.arg(2)?)?;
+            let act = activation(&options.flag("activation", "sigmoid".to_string())?)?;
+            run_worker(options.arg(1)?, act, |g| task.fitness(g))
+        }
+        "dot" => {
+            let genome = Genome::from_text(&read(options.arg(1)?)?, sigmoid)?;
+            match options.flags.get("output") {
+                Some(path) => write(Path::new(path), &genome.to_dot()),
+                None => {
+                    print!("{}", genome.to_dot());
+                    Ok(())
+                }
+            }
+        }
+        _ => Err(NeatError::Parse(USAGE.to_string())),
+    }
+}
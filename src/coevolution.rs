@@ -0,0 +1,114 @@
+use crate::constants::{COEVOLUTION_SAMPLES, HALL_SAMPLES, HALL_SIZE};
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::helper::rand_i32;
+use crate::population::Population;
+
+pub struct Coevolution {
+    //genomes of first always play as the first player of a match
+    pub first: Population,
+    pub second: Population,
+    //opponents sampled from the current generation of the other population
+    pub samples: usize,
+    //opponents sampled from the hall of fame of the other population
+    pub hall_samples: usize,
+    //oldest champions are forgotten past this size
+    pub hall_size: usize,
+    pub first_hall: Vec<Genome>,
+    pub second_hall: Vec<Genome>,
+    //given both players returns the score of the first and second player
+    pub play: fn(&Genome, &Genome) -> (f64, f64),
+}
+
+impl Coevolution {
+    pub fn new(
+        first: Population,
+        second: Population,
+        play: fn(&Genome, &Genome) -> (f64, f64),
+    ) -> Self {
+        Self {
+            first: first,
+            second: second,
+            samples: COEVOLUTION_SAMPLES,
+            hall_samples: HALL_SAMPLES,
+            hall_size: HALL_SIZE,
+            first_hall: vec![],
+            second_hall: vec![],
+            play: play,
+        }
+    }
+
+    fn opponents<'a>(&self, current: &'a [Genome], hall: &'a [Genome]) -> Vec<&'a Genome> {
+        //random opponents from the current generation followed by random past champions
+        let mut opponents: Vec<&Genome> = vec![];
+        if !current.is_empty() {
+            for _ in 0..self.samples {
+                opponents.push(&current[rand_i32(0, current.len() as i32 - 1) as usize]);
+            }
+        }
+        if !hall.is_empty() {
+            for _ in 0..self.hall_samples {
+                opponents.push(&hall[rand_i32(0, hall.len() as i32 - 1) as usize]);
+            }
+        }
+        opponents
+    }
+
+    pub fn evaluate(&self) -> (Vec<f64>, Vec<f64>) {
+        //Mean score of every genome of both populations against its sampled opponents
+        let mut first_fitness: Vec<f64> = vec![];
+        for g in &self.first.population {
+            let opponents = self.opponents(&self.second.population, &self.second_hall);
+            let total: f64 = opponents.iter().map(|o| (self.play)(g, o).0).sum();
+            first_fitness.push(total / (opponents.len().max(1) as f64));
+        }
+        let mut second_fitness: Vec<f64> = vec![];
+        for g in &self.second.population {
+            let opponents = self.opponents(&self.first.population, &self.first_hall);
+            let total: f64 = opponents.iter().map(|o| (self.play)(o, g).1).sum();
+            second_fitness.push(total / (opponents.len().max(1) as f64));
+        }
+        (first_fitness, second_fitness)
+    }
+
+    fn champion(population: &Population, fitness: &[f64]) -> Option<Genome> {
+        let best = fitness
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(idx, _)| idx)?;
+        Some(population.population[best].clone())
+    }
+
+    fn induct(hall: &mut Vec<Genome>, champion: Option<Genome>, hall_size: usize) {
+        if let Some(c) = champion {
+            hall.push(c);
+        }
+        while hall.len() > hall_size {
+            hall.remove(0);
+        }
+    }
+
+    pub fn next_generation(&mut self) -> (Vec<f64>, Vec<f64>) {
+        match self.try_next_generation() {
+            Ok(fitness) => fitness,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_next_generation(&mut self) -> Result<(Vec<f64>, Vec<f64>), NeatError> {
+        //Play a round of matches, store both champions in the hall of fame and advance
+        //each population with its own fitness, returns the fitness of the round
+        let (mut first_fitness, mut second_fitness) = self.evaluate();
+        //check both before either population or hall of fame changes
+        self.first.check_fitness(&first_fitness)?;
+        self.second.check_fitness(&second_fitness)?;
+        let first_champion = Self::champion(&self.first, &first_fitness);
+        let second_champion = Self::champion(&self.second, &second_fitness);
+        Self::induct(&mut self.first_hall, first_champion, self.hall_size);
+        Self::induct(&mut self.second_hall, second_champion, self.hall_size);
+        self.first.try_next_generation(&mut first_fitness)?;
+        self.second.try_next_generation(&mut second_fitness)?;
+        Ok((first_fitness, second_fitness))
+    }
+}
@@ -0,0 +1,201 @@
+use std::str::FromStr;
+
+use crate::constants::*;
+use crate::error::NeatError;
+use crate::fitness::FitnessPolicy;
+use crate::selection::{Rank, Roulette, SelectionStrategy, Tournament, Truncation};
+
+fn value<T: FromStr>(key: &str, value: &str) -> Result<T, NeatError> {
+    value
+        .parse()
+        .map_err(|_| NeatError::Parse(format!("invalid value {} for {}", value, key)))
+}
+
+fn count(key: &str, v: &str) -> Result<i32, NeatError> {
+    let n: i32 = value(key, v)?;
+    if n < 0 {
+        return Err(NeatError::InvalidConfig(format!(
+            "{} {} is negative",
+            key, n
+        )));
+    }
+    Ok(n)
+}
+
+pub struct Config {
+    //generations a species may go without improving before it is removed
+    pub stagnation_limit: i32,
+    //the best species by fitness are never removed for stagnating
+    pub species_elitism: i32,
+    //generations the whole population may go without improving
+    pub population_stagnation: i32,
+    //number of species allowed to reproduce once the population stagnates
+    pub stagnant_survivors: i32,
+    //starting compatibility threshold used to split genomes into species
+    pub compatibility_threshold: f64,
+    //when set the threshold is adjusted every generation to hold this many species
+    pub target_species: Option<i32>,
+    pub threshold_min: f64,
+    pub threshold_max: f64,
+    pub threshold_step: f64,
+    //chance that a crossover takes its second parent from another species
+    pub interspecies_rate: f64,
+    //how parents are picked from the members of a species
+    pub selection: Box<dyn SelectionStrategy>,
+    //champions copied unchanged from each species into the next generation
+    pub elitism: i32,
+    //species smaller than this don't keep their champions
+    pub elitism_min_size: i32,
+    //best genomes of the whole population always copied into the next generation
+    pub global_elitism: i32,
+    //offspring every surviving species is guaranteed
+    pub min_offspring: i32,
+    //how NaN, infinite and negative fitness is handled before fitness sharing,
+    //shifting by default so the infallible next_generation never sees invalid values
+    pub fitness_policy: FitnessPolicy,
+    //validate every offspring after mutation, useful when debugging
+    pub validate_genomes: bool,
+    //nearest behaviors averaged to measure novelty
+    pub novelty_neighbours: usize,
+    //behaviors more novel than this are added to the archive
+    pub archive_threshold: f64,
+    //share of novelty in the blended score, 1.0 is pure novelty search
+    pub novelty_weight: f64,
+    //add fewer connections and fewer nodes as objectives in multi-objective runs
+    pub complexity_objective: bool,
+    //fitness subtracted per active connection and per hidden node before fitness sharing
+    pub connection_cost: f64,
+    pub node_cost: f64,
+    //scale the costs by the mean complexity of the population relative to a
+    //fully connected genome without hidden nodes
+    pub adaptive_parsimony: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            stagnation_limit: STAGNATION_LIMIT,
+            species_elitism: SPECIES_ELITISM,
+            population_stagnation: POPULATION_STAGNATION,
+            stagnant_survivors: STAGNANT_SURVIVORS,
+            compatibility_threshold: DT,
+            target_species: None,
+            threshold_min: DT_MIN,
+            threshold_max: DT_MAX,
+            threshold_step: DT_STEP,
+            interspecies_rate: INTERSPECIES_RATE,
+            selection: Box::new(Truncation { fraction: TOP_ONES }),
+            elitism: ELITISM,
+            elitism_min_size: ELITISM_MIN_SIZE,
+            global_elitism: 0,
+            min_offspring: 0,
+            fitness_policy: FitnessPolicy::Shift,
+            validate_genomes: false,
+            novelty_neighbours: NOVELTY_NEIGHBOURS,
+            archive_threshold: ARCHIVE_THRESHOLD,
+            novelty_weight: 1.0,
+            complexity_objective: false,
+            connection_cost: 0.0,
+            node_cost: 0.0,
+            adaptive_parsimony: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn set(&mut self, key: &str, v: &str) -> Result<(), NeatError> {
+        //Set a field from its name and textual value, used for key = value config files.
+        //selection is one of truncation:<fraction>, tournament:<size>, roulette or rank,
+        //fitness_policy one of reject, clamp:<min> or shift and target_species may be none
+        let (kind, arg) = match v.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg)),
+            None => (v, None),
+        };
+        match key {
+            "stagnation_limit" => self.stagnation_limit = value(key, v)?,
+            "species_elitism" => self.species_elitism = value(key, v)?,
+            "population_stagnation" => self.population_stagnation = value(key, v)?,
+            "stagnant_survivors" => self.stagnant_survivors = value(key, v)?,
+            "compatibility_threshold" => self.compatibility_threshold = value(key, v)?,
+            "target_species" if v == "none" => self.target_species = None,
+            "target_species" => self.target_species = Some(value(key, v)?),
+            "threshold_min" => self.threshold_min = value(key, v)?,
+            "threshold_max" => self.threshold_max = value(key, v)?,
+            "threshold_step" => self.threshold_step = value(key, v)?,
+            "interspecies_rate" => self.interspecies_rate = value(key, v)?,
+            "selection" => {
+                self.selection = match (kind, arg) {
+                    ("truncation", Some(a)) => Box::new(Truncation {
+                        fraction: value(key, a)?,
+                    }),
+                    ("truncation", None) => Box::new(Truncation { fraction: TOP_ONES }),
+                    ("tournament", Some(a)) => Box::new(Tournament {
+                        size: value(key, a)?,
+                    }),
+                    ("roulette", None) => Box::new(Roulette),
+                    ("rank", None) => Box::new(Rank),
+                    _ => return Err(NeatError::Parse(format!("unknown selection {}", v))),
+                }
+            }
+            "elitism" => self.elitism = count(key, v)?,
+            "elitism_min_size" => self.elitism_min_size = count(key, v)?,
+            "global_elitism" => self.global_elitism = count(key, v)?,
+            "min_offspring" => self.min_offspring = count(key, v)?,
+            "fitness_policy" => {
+                self.fitness_policy = match (kind, arg) {
+                    ("reject", None) => FitnessPolicy::Reject,
+                    ("clamp", Some(a)) => {
+                        let minimum: f64 = value(key, a)?;
+                        if minimum.is_nan() || minimum < 0.0 {
+                            return Err(NeatError::InvalidConfig(format!(
+                                "clamp minimum {} is negative",
+                                minimum
+                            )));
+                        }
+                        FitnessPolicy::Clamp(minimum)
+                    }
+                    ("clamp", None) => FitnessPolicy::Clamp(0.0),
+                    ("shift", None) => FitnessPolicy::Shift,
+                    _ => return Err(NeatError::Parse(format!("unknown fitness policy {}", v))),
+                }
+            }
+            "validate_genomes" => self.validate_genomes = value(key, v)?,
+            "novelty_neighbours" => self.novelty_neighbours = value(key, v)?,
+            "archive_threshold" => self.archive_threshold = value(key, v)?,
+            "novelty_weight" => self.novelty_weight = value(key, v)?,
+            "complexity_objective" => self.complexity_objective = value(key, v)?,
+            "connection_cost" => self.connection_cost = value(key, v)?,
+            "node_cost" => self.node_cost = value(key, v)?,
+            "adaptive_parsimony" => self.adaptive_parsimony = value(key, v)?,
+            _ => return Err(NeatError::Parse(format!("unknown config key {}", key))),
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), NeatError> {
+        //Check the settings that only make sense together, called once they are all set
+        if self.threshold_min.is_nan()
+            || self.threshold_max.is_nan()
+            || self.threshold_min > self.threshold_max
+        {
+            return Err(NeatError::InvalidConfig(format!(
+                "threshold_min {} is not at most threshold_max {}",
+                self.threshold_min, self.threshold_max
+            )));
+        }
+        for (key, n) in [
+            ("elitism", self.elitism),
+            ("elitism_min_size", self.elitism_min_size),
+            ("global_elitism", self.global_elitism),
+            ("min_offspring", self.min_offspring),
+        ] {
+            if n < 0 {
+                return Err(NeatError::InvalidConfig(format!(
+                    "{} {} is negative",
+                    key, n
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -10,3 +10,26 @@ pub const PERTUBE_EDGES: f64 = 0.10;
 pub const RANDOM_EDGE: f64 = 0.30;
 pub const RANDOM_SPLIT: f64 = 0.05;
 pub const MAX_WEIGHT: f64 = 8.0;
+pub const STAGNATION_LIMIT: i32 = 15;
+pub const SPECIES_ELITISM: i32 = 2;
+pub const POPULATION_STAGNATION: i32 = 20;
+pub const STAGNANT_SURVIVORS: i32 = 2;
+pub const DT_MIN: f64 = 0.3;
+pub const DT_MAX: f64 = 10.0;
+pub const DT_STEP: f64 = 0.3;
+pub const INTERSPECIES_RATE: f64 = 0.001;
+pub const ELITISM: i32 = 1;
+pub const ELITISM_MIN_SIZE: i32 = 6;
+pub const WEIGHT_THRESHOLD: f64 = 0.2;
+pub const SUBSTRATE_WEIGHT: f64 = 3.0;
+pub const INITIAL_DEPTH: i32 = 3;
+pub const MAX_DEPTH: i32 = 5;
+pub const DIVISION_THRESHOLD: f64 = 0.03;
+pub const VARIANCE_THRESHOLD: f64 = 0.03;
+pub const BAND_THRESHOLD: f64 = 0.3;
+pub const ITERATION_LEVEL: i32 = 1;
+pub const NOVELTY_NEIGHBOURS: usize = 15;
+pub const ARCHIVE_THRESHOLD: f64 = 1.0;
+pub const COEVOLUTION_SAMPLES: usize = 5;
+pub const HALL_SAMPLES: usize = 5;
+pub const HALL_SIZE: usize = 50;
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::helper::shuffle;
+use crate::population::Population;
+
+pub fn compose(modules: &[&Genome]) -> Result<Genome, NeatError> {
+    //Merge modules sharing the same inputs into one network whose outputs are the
+    //outputs of every module in order, hidden nodes and innovations are renumbered.
+    //A genome has a single activation so the team uses the one of the first module
+    let first = modules.first().ok_or(NeatError::InvalidGenome(
+        "no modules to compose".to_string(),
+    ))?;
+    let inputs = first.input_nodes;
+    let outputs: i32 = modules.iter().map(|m| m.output_nodes).sum();
+    let mut team = Genome::new(inputs, outputs, first.act);
+    let mut next_node = inputs + outputs + 1;
+    let mut inno = 0;
+    let mut offset = 0;
+    for m in modules {
+        if m.input_nodes != inputs {
+            return Err(NeatError::InvalidGenome(format!(
+                "module has {} inputs, expected {}",
+                m.input_nodes, inputs
+            )));
+        }
+        //global id inside the module to local id inside the team
+        let mut mapping: BTreeMap<i32, i32> = BTreeMap::new();
+        for (i, n) in m.nodes.iter().enumerate() {
+            let i = i as i32;
+            let local = if i <= inputs {
+                i
+            } else if i < inputs + 1 + m.output_nodes {
+                i + offset
+            } else {
+                next_node += 1;
+                team.add_node(next_node - 1)
+            };
+            mapping.insert(n.borrow().global_id, local);
+        }
+        for g in m.flatten() {
+            let u = *mapping.get(&g.from).ok_or(NeatError::UnknownNode(g.from))?;
+            let v = *mapping.get(&g.to).ok_or(NeatError::UnknownNode(g.to))?;
+            team.try_add_edge(u, v, inno, g.weight, g.active)?;
+            inno += 1;
+        }
+        offset += m.output_nodes;
+    }
+    Ok(team)
+}
+
+pub struct Cooperative {
+    //one population per module, a team takes one genome from each
+    pub modules: Vec<Population>,
+    //minimum number of teams every genome takes part in per generation
+    pub trials: usize,
+    //fitness of an assembled team
+    pub evaluate: fn(&Genome) -> f64,
+    //best team seen so far with its fitness
+    pub best: Option<(f64, Genome)>,
+}
+
+impl Cooperative {
+    pub fn new(modules: Vec<Population>, evaluate: fn(&Genome) -> f64) -> Self {
+        match Cooperative::try_new(modules, evaluate) {
+            Ok(c) => c,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_new(
+        modules: Vec<Population>,
+        evaluate: fn(&Genome) -> f64,
+    ) -> Result<Self, NeatError> {
+        //Every team needs a genome from each module so none of them can be empty
+        if modules.is_empty() {
+            return Err(NeatError::InvalidConfig(
+                "no module populations".to_string(),
+            ));
+        }
+        if let Some(m) = modules.iter().position(|p| p.population.is_empty()) {
+            return Err(NeatError::InvalidConfig(format!(
+                "module population {} is empty",
+                m
+            )));
+        }
+        Ok(Self {
+            modules: modules,
+            trials: 10,
+            evaluate: evaluate,
+            best: None,
+        })
+    }
+
+    pub fn teams(&self) -> Vec<Vec<usize>> {
+        //Every trial shuffles each population and lines them up, smaller populations
+        //wrap around so every genome plays at least once per trial
+        let longest = self
+            .modules
+            .iter()
+            .map(|p| p.population.len())
+            .max()
+            .unwrap_or(0);
+        let mut teams: Vec<Vec<usize>> = vec![];
+        for _ in 0..self.trials {
+            let mut orders: Vec<Vec<usize>> = vec![];
+            for p in &self.modules {
+                let mut order: Vec<usize> = (0..p.population.len()).collect();
+                shuffle(&mut order);
+                orders.push(order);
+            }
+            for t in 0..longest {
+                teams.push(orders.iter().map(|o| o[t % o.len()]).collect());
+            }
+        }
+        teams
+    }
+
+    pub fn try_evaluate(&mut self) -> Result<Vec<Vec<f64>>, NeatError> {
+        //Evaluate sampled teams and credit every participant with the mean fitness
+        //of the teams it was part of
+        let mut total: Vec<Vec<f64>> = vec![];
+        let mut played: Vec<Vec<i32>> = vec![];
+        for p in &self.modules {
+            total.push(vec![0.0; p.population.len()]);
+            played.push(vec![0; p.population.len()]);
+        }
+        for team in self.teams() {
+            let members: Vec<&Genome> = team
+                .iter()
+                .enumerate()
+                .map(|(s, g)| &self.modules[s].population[*g])
+                .collect();
+            let network = compose(&members)?;
+            let fit = (self.evaluate)(&network);
+            for (s, g) in team.iter().enumerate() {
+                total[s][*g] += fit;
+                played[s][*g] += 1;
+            }
+            if self.best.as_ref().map_or(true, |b| fit > b.0) {
+                self.best = Some((fit, network));
+            }
+        }
+        for s in 0..total.len() {
+            for g in 0..total[s].len() {
+                total[s][g] /= f64::max(played[s][g] as f64, 1.0);
+            }
+        }
+        Ok(total)
+    }
+
+    pub fn next_generation(&mut self) -> Vec<Vec<f64>> {
+        match self.try_next_generation() {
+            Ok(fitness) => fitness,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_next_generation(&mut self) -> Result<Vec<Vec<f64>>, NeatError> {
+        //Credit fitness back to every module population and advance each of them
+        let mut fitness = self.try_evaluate()?;
+        //every module is checked first so a bad one doesn't leave the rest a generation ahead
+        for (p, f) in self.modules.iter().zip(&fitness) {
+            p.check_fitness(f)?;
+        }
+        for (p, f) in self.modules.iter_mut().zip(fitness.iter_mut()) {
+            p.try_next_generation(f)?;
+        }
+        Ok(fitness)
+    }
+}
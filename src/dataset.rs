@@ -0,0 +1,279 @@
+use std::fs;
+
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::helper::shuffle;
+use crate::population::Population;
+
+pub struct Dataset {
+    pub inputs: Vec<Vec<f64>>,
+    pub targets: Vec<Vec<f64>>,
+}
+
+impl Dataset {
+    pub fn new(inputs: Vec<Vec<f64>>, targets: Vec<Vec<f64>>) -> Self {
+        match Dataset::try_new(inputs, targets) {
+            Ok(d) => d,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_new(inputs: Vec<Vec<f64>>, targets: Vec<Vec<f64>>) -> Result<Self, NeatError> {
+        //every row of inputs needs a row of targets
+        if inputs.len() != targets.len() {
+            return Err(NeatError::TargetLength {
+                expected: inputs.len(),
+                found: targets.len(),
+            });
+        }
+        Ok(Self {
+            inputs: inputs,
+            targets: targets,
+        })
+    }
+
+    pub fn parse(text: &str, targets: usize) -> Result<Self, NeatError> {
+        //Comma separated rows where the last `targets` columns are the targets,
+        //a non-numeric first line is a header, empty lines and lines starting with #
+        //are skipped and don't count as the first line
+        let mut data = Dataset::new(vec![], vec![]);
+        let mut columns: Option<usize> = None;
+        let mut first = true;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let header = first;
+            first = false;
+            let row: Result<Vec<f64>, _> = line.split(',').map(|v| v.trim().parse()).collect();
+            let row = match row {
+                Ok(row) => row,
+                Err(_) if header => continue,
+                Err(_) => {
+                    return Err(NeatError::Parse(format!(
+                        "non-numeric value on line {}",
+                        i + 1
+                    )))
+                }
+            };
+            if row.len() != *columns.get_or_insert(row.len()) {
+                return Err(NeatError::Parse(format!(
+                    "line {} has {} columns, expected {}",
+                    i + 1,
+                    row.len(),
+                    columns.unwrap()
+                )));
+            }
+            if row.len() <= targets {
+                return Err(NeatError::Parse(format!(
+                    "line {} has no input columns",
+                    i + 1
+                )));
+            }
+            let split = row.len() - targets;
+            data.inputs.push(row[..split].to_vec());
+            data.targets.push(row[split..].to_vec());
+        }
+        Ok(data)
+    }
+
+    pub fn load(path: &str, targets: usize) -> Result<Self, NeatError> {
+        let text =
+            fs::read_to_string(path).map_err(|e| NeatError::Io(format!("{}: {}", path, e)))?;
+        Dataset::parse(&text, targets)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+
+    pub fn split(&self, validation: f64) -> (Dataset, Dataset) {
+        match self.try_split(validation) {
+            Ok(sets) => sets,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_split(&self, validation: f64) -> Result<(Dataset, Dataset), NeatError> {
+        //Random train and validation sets, validation holds the given fraction of rows
+        if !(0.0..=1.0).contains(&validation) {
+            return Err(NeatError::InvalidConfig(format!(
+                "validation fraction {} is not between 0 and 1",
+                validation
+            )));
+        }
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        shuffle(&mut order);
+        let count = (self.len() as f64 * validation).round() as usize;
+        let pick = |idx: &[usize]| -> Dataset {
+            Dataset::new(
+                idx.iter().map(|i| self.inputs[*i].clone()).collect(),
+                idx.iter().map(|i| self.targets[*i].clone()).collect(),
+            )
+        };
+        Ok((pick(&order[count..]), pick(&order[..count])))
+    }
+}
+
+pub enum Loss {
+    MeanSquared,
+    MeanAbsolute,
+    //binary for a single output, categorical over softmaxed outputs otherwise
+    CrossEntropy,
+    //output above 0.5 for a single output, largest output otherwise
+    Accuracy,
+}
+
+fn argmax(v: &[f64]) -> usize {
+    v.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+}
+
+impl Loss {
+    pub fn error(&self, outputs: &[f64], targets: &[f64]) -> f64 {
+        match self.try_error(outputs, targets) {
+            Ok(e) => e,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_error(&self, outputs: &[f64], targets: &[f64]) -> Result<f64, NeatError> {
+        //Loss of a single row, for accuracy 1 if the row is classified correctly
+        if outputs.len() != targets.len() {
+            return Err(NeatError::TargetLength {
+                expected: outputs.len(),
+                found: targets.len(),
+            });
+        }
+        let n = outputs.len() as f64;
+        let eps = 1e-12;
+        let error = match self {
+            Loss::MeanSquared => {
+                outputs
+                    .iter()
+                    .zip(targets)
+                    .map(|(o, t)| (o - t) * (o - t))
+                    .sum::<f64>()
+                    / n
+            }
+            Loss::MeanAbsolute => {
+                outputs
+                    .iter()
+                    .zip(targets)
+                    .map(|(o, t)| (o - t).abs())
+                    .sum::<f64>()
+                    / n
+            }
+            Loss::CrossEntropy if outputs.len() == 1 => {
+                let o = outputs[0].clamp(eps, 1.0 - eps);
+                -(targets[0] * o.ln() + (1.0 - targets[0]) * (1.0 - o).ln())
+            }
+            Loss::CrossEntropy => {
+                let max = outputs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                let total: f64 = outputs.iter().map(|o| (o - max).exp()).sum();
+                outputs
+                    .iter()
+                    .zip(targets)
+                    .map(|(o, t)| -t * f64::max((o - max).exp() / total, eps).ln())
+                    .sum()
+            }
+            Loss::Accuracy if outputs.len() == 1 => {
+                ((outputs[0] > 0.5) == (targets[0] > 0.5)) as i32 as f64
+            }
+            Loss::Accuracy => (argmax(outputs) == argmax(targets)) as i32 as f64,
+        };
+        Ok(error)
+    }
+
+    pub fn score(&self, genome: &Genome, data: &Dataset) -> f64 {
+        match self.try_score(genome, data) {
+            Ok(score) => score,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_score(&self, genome: &Genome, data: &Dataset) -> Result<f64, NeatError> {
+        //Mean loss, or accuracy, of the genome over every row, fails if the rows don't
+        //match the inputs or outputs of the genome
+        let mut total: f64 = 0.0;
+        for (input, target) in data.inputs.iter().zip(&data.targets) {
+            total += self.try_error(&genome.try_evaluate(input)?, target)?;
+        }
+        Ok(total / f64::max(data.len() as f64, 1.0))
+    }
+
+    pub fn fitness(&self, score: f64) -> f64 {
+        //Non-negative fitness where higher is better
+        match self {
+            Loss::Accuracy => score,
+            _ => 1.0 / (1.0 + score),
+        }
+    }
+}
+
+pub struct Supervised {
+    pub train: Dataset,
+    pub validation: Dataset,
+    pub loss: Loss,
+}
+
+impl Supervised {
+    pub fn new(data: &Dataset, validation: f64, loss: Loss) -> Self {
+        match Supervised::try_new(data, validation, loss) {
+            Ok(task) => task,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_new(data: &Dataset, validation: f64, loss: Loss) -> Result<Self, NeatError> {
+        let (train, validation) = data.try_split(validation)?;
+        Ok(Self {
+            train: train,
+            validation: validation,
+            loss: loss,
+        })
+    }
+
+    pub fn without_validation(data: Dataset, loss: Loss) -> Self {
+        //train on every row in its original order
+        Self {
+            train: data,
+            validation: Dataset::new(vec![], vec![]),
+            loss: loss,
+        }
+    }
+
+    pub fn fitness(&self, genome: &Genome) -> f64 {
+        self.loss.fitness(self.loss.score(genome, &self.train))
+    }
+
+    pub fn try_fitness(&self, genome: &Genome) -> Result<f64, NeatError> {
+        Ok(self.loss.fitness(self.loss.try_score(genome, &self.train)?))
+    }
+
+    pub fn evaluate_population(&self, population: &Population) -> Vec<f64> {
+        population
+            .population
+            .iter()
+            .map(|g| self.fitness(g))
+            .collect()
+    }
+
+    pub fn validation_score(&self, genome: &Genome) -> f64 {
+        self.loss.score(genome, &self.validation)
+    }
+
+    pub fn champion(&self, population: &Population, fitness: &[f64]) -> (usize, f64) {
+        //Index of the fittest genome on the training set and its validation loss
+        let best = argmax(fitness);
+        (best, self.validation_score(&population.population[best]))
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::NeatError;
+use crate::genome::Genome;
+
+//Protocol: one line per message, the coordinator sends "<index> <genome text>"
+//and the worker answers "<index> <fitness>"
+
+struct Jobs {
+    //genome index, serialized genome and attempts so far
+    queue: VecDeque<(usize, String, i32)>,
+    //jobs that are neither answered nor abandoned
+    remaining: usize,
+}
+
+fn network(e: io::Error) -> NeatError {
+    NeatError::Network(e.to_string())
+}
+
+pub struct Coordinator {
+    pub listener: TcpListener,
+    pub workers: Vec<TcpStream>,
+    //a worker taking longer than this to answer is considered dead
+    pub timeout: Duration,
+    //times a genome is handed to another worker after its worker died
+    pub retries: i32,
+}
+
+impl Coordinator {
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self, NeatError> {
+        Ok(Self {
+            listener: TcpListener::bind(addr).map_err(network)?,
+            workers: vec![],
+            timeout: Duration::from_secs(60),
+            retries: 3,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, NeatError> {
+        self.listener.local_addr().map_err(network)
+    }
+
+    pub fn accept(&mut self, workers: usize) -> Result<(), NeatError> {
+        //Block until the given number of workers connected
+        for _ in 0..workers {
+            let (stream, _) = self.listener.accept().map_err(network)?;
+            //messages are tiny request/answer pairs, don't let Nagle delay them
+            stream.set_nodelay(true).map_err(network)?;
+            self.workers.push(stream);
+        }
+        Ok(())
+    }
+
+    pub fn evaluate(&mut self, genomes: &[Genome]) -> Result<Vec<f64>, NeatError> {
+        //Hand genomes to idle workers until every fitness is known, genomes of dead or
+        //timed out workers go back to the queue and the worker is dropped
+        if self.workers.is_empty() {
+            return Err(NeatError::Network("no workers connected".to_string()));
+        }
+        let jobs = Arc::new((
+            Mutex::new(Jobs {
+                queue: genomes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, g)| (i, g.to_text(), 0))
+                    .collect(),
+                remaining: genomes.len(),
+            }),
+            Condvar::new(),
+        ));
+        let (tx, rx) = mpsc::channel();
+        let mut handles = vec![];
+        for stream in self.workers.drain(..) {
+            let jobs = jobs.clone();
+            let tx = tx.clone();
+            let timeout = self.timeout;
+            let retries = self.retries;
+            handles.push(thread::spawn(move || {
+                serve_jobs(stream, jobs, tx, timeout, retries)
+            }));
+        }
+        drop(tx);
+        let mut fitness: Vec<f64> = vec![f64::NAN; genomes.len()];
+        let mut done: usize = 0;
+        let mut error: Option<NeatError> = None;
+        for r in rx {
+            match r {
+                Ok((i, f)) => {
+                    fitness[i] = f;
+                    done += 1;
+                }
+                Err(e) => error = Some(e),
+            }
+        }
+        for h in handles {
+            if let Ok(Some(stream)) = h.join() {
+                self.workers.push(stream);
+            }
+        }
+        if let Some(e) = error {
+            return Err(e);
+        }
+        if done < genomes.len() {
+            return Err(NeatError::Network(format!(
+                "every worker died with {} genomes left",
+                genomes.len() - done
+            )));
+        }
+        Ok(fitness)
+    }
+}
+
+fn request(
+    stream: &mut TcpStream,
+    reader: &mut BufReader<TcpStream>,
+    idx: usize,
+    text: &str,
+) -> io::Result<f64> {
+    stream.write_all(format!("{} {}\n", idx, text).as_bytes())?;
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "worker closed",
+        ));
+    }
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed answer");
+    let (answer, fitness) = line.trim().split_once(' ').ok_or_else(invalid)?;
+    if answer.parse::<usize>().ok() != Some(idx) {
+        return Err(invalid());
+    }
+    fitness.parse().map_err(|_| invalid())
+}
+
+fn serve_jobs(
+    mut stream: TcpStream,
+    jobs: Arc<(Mutex<Jobs>, Condvar)>,
+    results: Sender<Result<(usize, f64), NeatError>>,
+    timeout: Duration,
+    retries: i32,
+) -> Option<TcpStream> {
+    //Feed one worker until no job is left, returns the stream if the worker survived
+    stream.set_read_timeout(Some(timeout)).ok()?;
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let (lock, changed) = &*jobs;
+    loop {
+        let (idx, text, attempts) = {
+            let mut jobs = lock.lock().unwrap();
+            //other workers still hold jobs that may come back, wait until one does
+            //or every job is answered
+            while jobs.remaining > 0 && jobs.queue.is_empty() {
+                jobs = changed.wait(jobs).unwrap();
+            }
+            match jobs.queue.pop_front() {
+                Some(job) => job,
+                None => return Some(stream),
+            }
+        };
+        match request(&mut stream, &mut reader, idx, &text) {
+            Ok(f) => {
+                lock.lock().unwrap().remaining -= 1;
+                changed.notify_all();
+                let _ = results.send(Ok((idx, f)));
+            }
+            Err(_) => {
+                let mut jobs = lock.lock().unwrap();
+                if attempts >= retries {
+                    jobs.remaining -= 1;
+                    let _ = results.send(Err(NeatError::Network(format!(
+                        "genome {} failed after {} attempts",
+                        idx,
+                        attempts + 1
+                    ))));
+                } else {
+                    jobs.queue.push_back((idx, text, attempts + 1));
+                }
+                changed.notify_all();
+                return None;
+            }
+        }
+    }
+}
+
+pub fn run_worker<F: Fn(&Genome) -> f64>(
+    addr: impl ToSocketAddrs,
+    act: fn(f64) -> f64,
+    evaluate: F,
+) -> Result<(), NeatError> {
+    //Connect to a coordinator and answer genomes until it hangs up
+    let mut stream = TcpStream::connect(addr).map_err(network)?;
+    stream.set_nodelay(true).map_err(network)?;
+    let reader = BufReader::new(stream.try_clone().map_err(network)?);
+    for line in reader.lines() {
+        let line = line.map_err(network)?;
+        let (idx, text) = line
+            .split_once(' ')
+            .ok_or(NeatError::Parse(format!("malformed job {}", line)))?;
+        let genome = Genome::from_text(text, act)?;
+        let answer = format!("{} {}\n", idx, evaluate(&genome));
+        stream.write_all(answer.as_bytes()).map_err(network)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use crate::episode::{Environment, EpisodeRunner, FeedForward};
+use crate::genome::Genome;
+
+//physics constants shared by the cart-pole benchmarks
+const GRAVITY: f64 = 9.8;
+const MASS_CART: f64 = 1.0;
+const FORCE_MAG: f64 = 10.0;
+const TRACK_LIMIT: f64 = 2.4;
+//single pole, Barto et al. with Euler integration
+const MASS_POLE: f64 = 0.1;
+const HALF_LENGTH: f64 = 0.5;
+const TAU: f64 = 0.02;
+const TWELVE_DEGREES: f64 = 0.2094384;
+//double pole, Wieland's equations with Runge-Kutta integration
+const MASS_POLE_1: f64 = 0.1;
+const HALF_LENGTH_1: f64 = 0.5;
+const MASS_POLE_2: f64 = 0.01;
+const HALF_LENGTH_2: f64 = 0.05;
+const POLE_FRICTION: f64 = 0.000002;
+const RK4_TAU: f64 = 0.01;
+const THIRTY_SIX_DEGREES: f64 = 0.628329;
+//steps a controller has to balance to solve a task
+pub const BALANCE_STEPS: i32 = 100_000;
+//steps over which the non-Markov damping fitness is measured
+const DAMPING_WINDOW: usize = 100;
+const DAMPING_STEPS: i32 = 1000;
+
+pub struct CartPole {
+    //x, x velocity, theta, theta velocity
+    pub state: [f64; 4],
+    pub steps: i32,
+}
+
+impl Default for CartPole {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CartPole {
+    pub fn new() -> Self {
+        Self {
+            state: [0.0; 4],
+            steps: 0,
+        }
+    }
+
+    pub fn push(&mut self, action: f64) {
+        //bang-bang control, outputs above 0.5 push right
+        let force = if action > 0.5 { FORCE_MAG } else { -FORCE_MAG };
+        let [x, x_dot, theta, theta_dot] = self.state;
+        let total_mass = MASS_CART + MASS_POLE;
+        let pole_mass_length = MASS_POLE * HALF_LENGTH;
+        let cos_theta = f64::cos(theta);
+        let sin_theta = f64::sin(theta);
+        let temp = (force + pole_mass_length * theta_dot * theta_dot * sin_theta) / total_mass;
+        let theta_acc = (GRAVITY * sin_theta - cos_theta * temp)
+            / (HALF_LENGTH * (4.0 / 3.0 - MASS_POLE * cos_theta * cos_theta / total_mass));
+        let x_acc = temp - pole_mass_length * theta_acc * cos_theta / total_mass;
+        self.state = [
+            x + TAU * x_dot,
+            x_dot + TAU * x_acc,
+            theta + TAU * theta_dot,
+            theta_dot + TAU * theta_acc,
+        ];
+        self.steps += 1;
+    }
+
+    pub fn failed(&self) -> bool {
+        self.state[0].abs() > TRACK_LIMIT || self.state[2].abs() > TWELVE_DEGREES
+    }
+
+    pub fn run(&mut self, genome: &Genome, max_steps: i32) -> i32 {
+        //Steps the genome keeps the pole up, starting from rest
+        EpisodeRunner::new(1, max_steps).run(self, &mut FeedForward::new(genome)) as i32
+    }
+}
+
+impl Environment for CartPole {
+    fn reset(&mut self) {
+        self.state = [0.0; 4];
+        self.steps = 0;
+    }
+
+    fn observation(&self) -> Vec<f64> {
+        //state scaled to roughly [0, 1]
+        vec![
+            (self.state[0] + TRACK_LIMIT) / (2.0 * TRACK_LIMIT),
+            (self.state[1] + 0.75) / 1.5,
+            (self.state[2] + TWELVE_DEGREES) / 0.41,
+            (self.state[3] + 1.0) / 2.0,
+        ]
+    }
+
+    fn step(&mut self, action: &[f64]) -> (f64, bool) {
+        //one point for every step taken
+        self.push(action[0]);
+        (1.0, self.failed())
+    }
+}
+
+pub struct DoublePole {
+    //x, x velocity, theta 1, theta 1 velocity, theta 2, theta 2 velocity
+    pub state: [f64; 6],
+    //whether velocities are part of the observation
+    pub markov: bool,
+    pub steps: i32,
+}
+
+impl DoublePole {
+    pub fn new(markov: bool) -> Self {
+        let mut env = Self {
+            state: [0.0; 6],
+            markov: markov,
+            steps: 0,
+        };
+        env.reset();
+        env
+    }
+
+    fn derivatives(force: f64, s: &[f64; 6]) -> [f64; 6] {
+        let mut d = [s[1], 0.0, s[3], 0.0, s[5], 0.0];
+        let (cos_1, sin_1) = (f64::cos(s[2]), f64::sin(s[2]));
+        let (cos_2, sin_2) = (f64::cos(s[4]), f64::sin(s[4]));
+        let gsin_1 = -GRAVITY * sin_1;
+        let gsin_2 = -GRAVITY * sin_2;
+        let ml_1 = HALF_LENGTH_1 * MASS_POLE_1;
+        let ml_2 = HALF_LENGTH_2 * MASS_POLE_2;
+        let temp_1 = POLE_FRICTION * s[3] / ml_1;
+        let temp_2 = POLE_FRICTION * s[5] / ml_2;
+        //effective force and mass of each pole on the cart
+        let fi_1 = ml_1 * s[3] * s[3] * sin_1 + 0.75 * MASS_POLE_1 * cos_1 * (temp_1 + gsin_1);
+        let fi_2 = ml_2 * s[5] * s[5] * sin_2 + 0.75 * MASS_POLE_2 * cos_2 * (temp_2 + gsin_2);
+        let mi_1 = MASS_POLE_1 * (1.0 - 0.75 * cos_1 * cos_1);
+        let mi_2 = MASS_POLE_2 * (1.0 - 0.75 * cos_2 * cos_2);
+        d[1] = (force + fi_1 + fi_2) / (mi_1 + mi_2 + MASS_CART);
+        d[3] = -0.75 * (d[1] * cos_1 + gsin_1 + temp_1) / HALF_LENGTH_1;
+        d[5] = -0.75 * (d[1] * cos_2 + gsin_2 + temp_2) / HALF_LENGTH_2;
+        d
+    }
+
+    fn rk4(force: f64, s: &[f64; 6]) -> [f64; 6] {
+        let shift = |s: &[f64; 6], d: &[f64; 6], h: f64| -> [f64; 6] {
+            let mut out = *s;
+            for i in 0..6 {
+                out[i] += h * d[i];
+            }
+            out
+        };
+        let k1 = Self::derivatives(force, s);
+        let k2 = Self::derivatives(force, &shift(s, &k1, RK4_TAU / 2.0));
+        let k3 = Self::derivatives(force, &shift(s, &k2, RK4_TAU / 2.0));
+        let k4 = Self::derivatives(force, &shift(s, &k3, RK4_TAU));
+        let mut out = *s;
+        for i in 0..6 {
+            out[i] += RK4_TAU / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+        }
+        out
+    }
+
+    pub fn push(&mut self, action: f64) {
+        //continuous force from an output in [0, 1], integrated over two 0.01s steps
+        let force = (action.clamp(0.0, 1.0) - 0.5) * FORCE_MAG * 2.0;
+        for _ in 0..2 {
+            self.state = Self::rk4(force, &self.state);
+        }
+        self.steps += 1;
+    }
+
+    pub fn failed(&self) -> bool {
+        self.state[0].abs() > TRACK_LIMIT
+            || self.state[2].abs() > THIRTY_SIX_DEGREES
+            || self.state[4].abs() > THIRTY_SIX_DEGREES
+    }
+
+    pub fn run(&mut self, genome: &Genome, max_steps: i32) -> i32 {
+        //Steps the genome keeps both poles up
+        EpisodeRunner::new(1, max_steps).run(self, &mut FeedForward::new(genome)) as i32
+    }
+
+    pub fn damping_fitness(&mut self, genome: &Genome) -> f64 {
+        //Gruau's fitness for the non-Markov task, rewards balancing time and penalizes
+        //the cart and long pole oscillating during the last 100 steps
+        let mut damping = Damping {
+            pole: self,
+            wiggle: vec![],
+        };
+        EpisodeRunner::new(1, DAMPING_STEPS).run(&mut damping, &mut FeedForward::new(genome));
+        let wiggle = damping.wiggle;
+        let f1 = self.steps as f64 / DAMPING_STEPS as f64;
+        let f2 = if self.steps < DAMPING_WINDOW as i32 {
+            0.0
+        } else {
+            let recent: f64 = wiggle[wiggle.len() - DAMPING_WINDOW..].iter().sum();
+            0.75 / recent
+        };
+        0.1 * f1 + 0.9 * f2
+    }
+}
+
+struct Damping<'a> {
+    //records how much the cart and long pole move on every step survived
+    pole: &'a mut DoublePole,
+    wiggle: Vec<f64>,
+}
+
+impl Environment for Damping<'_> {
+    fn reset(&mut self) {
+        self.pole.reset();
+        self.wiggle.clear();
+    }
+
+    fn observation(&self) -> Vec<f64> {
+        self.pole.observation()
+    }
+
+    fn step(&mut self, action: &[f64]) -> (f64, bool) {
+        let (reward, failed) = self.pole.step(action);
+        if !failed {
+            let s = &self.pole.state;
+            self.wiggle
+                .push(s[0].abs() + s[1].abs() + s[2].abs() + s[3].abs());
+        }
+        (reward, failed)
+    }
+}
+
+impl Environment for DoublePole {
+    fn reset(&mut self) {
+        //long pole starts slightly tilted
+        self.state = [0.0, 0.0, 0.07, 0.0, 0.0, 0.0];
+        self.steps = 0;
+    }
+
+    fn observation(&self) -> Vec<f64> {
+        let s = &self.state;
+        if self.markov {
+            vec![
+                s[0] / 4.8,
+                s[1] / 2.0,
+                s[2] / 0.52,
+                s[3] / 2.0,
+                s[4] / 0.52,
+                s[5] / 2.0,
+            ]
+        } else {
+            vec![s[0] / 4.8, s[2] / 0.52, s[4] / 0.52]
+        }
+    }
+
+    fn step(&mut self, action: &[f64]) -> (f64, bool) {
+        self.push(action[0]);
+        (1.0, self.failed())
+    }
+}
+
+pub fn single_pole(genome: &Genome) -> f64 {
+    //Steps balanced, solved at BALANCE_STEPS
+    CartPole::new().run(genome, BALANCE_STEPS) as f64
+}
+
+pub fn double_pole_markov(genome: &Genome) -> f64 {
+    //Steps balanced with full state, solved at BALANCE_STEPS
+    DoublePole::new(true).run(genome, BALANCE_STEPS) as f64
+}
+
+pub fn double_pole_non_markov(genome: &Genome) -> f64 {
+    //Damping fitness without velocities, check DoublePole::run for success
+    DoublePole::new(false).damping_fitness(genome)
+}
@@ -0,0 +1,130 @@
+use crate::genome::Genome;
+use crate::population::Population;
+
+pub trait Environment {
+    //Start a new episode
+    fn reset(&mut self);
+    //Current state as the network sees it
+    fn observation(&self) -> Vec<f64>;
+    //Apply the network outputs, returns the reward of the step and whether the episode ended
+    fn step(&mut self, action: &[f64]) -> (f64, bool);
+}
+
+pub trait Controller {
+    //Forget any state carried between steps
+    fn reset(&mut self);
+    fn activate(&mut self, observation: &[f64]) -> Vec<f64>;
+}
+
+pub struct FeedForward<'a> {
+    pub genome: &'a Genome,
+}
+
+impl<'a> FeedForward<'a> {
+    pub fn new(genome: &'a Genome) -> Self {
+        Self { genome: genome }
+    }
+}
+
+impl Controller for FeedForward<'_> {
+    fn reset(&mut self) {}
+
+    fn activate(&mut self, observation: &[f64]) -> Vec<f64> {
+        self.genome.evaluate(&observation.to_vec())
+    }
+}
+
+pub struct Recurrent<'a> {
+    //takes the observation followed by its own previous outputs as inputs, only the
+    //outputs are remembered since a genome is feed-forward and hidden nodes are
+    //recomputed from scratch on every step
+    pub genome: &'a Genome,
+    pub memory: Vec<f64>,
+}
+
+impl<'a> Recurrent<'a> {
+    pub fn new(genome: &'a Genome) -> Self {
+        Self {
+            genome: genome,
+            memory: vec![0.0; genome.output_nodes as usize],
+        }
+    }
+}
+
+impl Controller for Recurrent<'_> {
+    fn reset(&mut self) {
+        self.memory = vec![0.0; self.genome.output_nodes as usize];
+    }
+
+    fn activate(&mut self, observation: &[f64]) -> Vec<f64> {
+        let mut input = observation.to_vec();
+        input.extend(&self.memory);
+        self.memory = self.genome.evaluate(&input);
+        self.memory.clone()
+    }
+}
+
+pub struct EpisodeRunner {
+    pub episodes: i32,
+    //an episode that doesn't end by itself is cut after this many steps
+    pub max_steps: i32,
+    //feed previous outputs (not hidden node values) back as extra inputs, see Recurrent
+    pub recurrent: bool,
+}
+
+impl EpisodeRunner {
+    pub fn new(episodes: i32, max_steps: i32) -> Self {
+        Self {
+            episodes: episodes,
+            max_steps: max_steps,
+            recurrent: false,
+        }
+    }
+
+    pub fn episode(&self, env: &mut dyn Environment, controller: &mut dyn Controller) -> f64 {
+        //Total reward of a single episode
+        env.reset();
+        controller.reset();
+        let mut total: f64 = 0.0;
+        for _ in 0..self.max_steps {
+            let action = controller.activate(&env.observation());
+            let (reward, done) = env.step(&action);
+            total += reward;
+            if done {
+                break;
+            }
+        }
+        total
+    }
+
+    pub fn run(&self, env: &mut dyn Environment, controller: &mut dyn Controller) -> f64 {
+        //Mean total reward over all episodes
+        let mut total: f64 = 0.0;
+        for _ in 0..self.episodes {
+            total += self.episode(env, controller);
+        }
+        total / f64::max(self.episodes as f64, 1.0)
+    }
+
+    pub fn fitness(&self, env: &mut dyn Environment, genome: &Genome) -> f64 {
+        if self.recurrent {
+            self.run(env, &mut Recurrent::new(genome))
+        } else {
+            self.run(env, &mut FeedForward::new(genome))
+        }
+    }
+
+    pub fn evaluate_population(
+        &self,
+        env: &mut dyn Environment,
+        population: &Population,
+    ) -> Vec<f64> {
+        //Fitness vector ready for Population::next_generation, rewards should be
+        //non-negative unless the fitness policy of the population handles it
+        population
+            .population
+            .iter()
+            .map(|g| self.fitness(env, g))
+            .collect()
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NeatError {
+    //an edge from a node to itself
+    SelfLoop(i32),
+    //a local node id that is not part of the genome
+    NodeOutOfRange {
+        node: i32,
+        nodes: i32,
+    },
+    //a global node id that can't be placed in the genome
+    UnknownNode(i32),
+    InputLength {
+        expected: usize,
+        found: usize,
+    },
+    FitnessLength {
+        expected: usize,
+        found: usize,
+    },
+    //a row of targets that doesn't match the outputs or rows it belongs to
+    TargetLength {
+        expected: usize,
+        found: usize,
+    },
+    InvalidFitness {
+        index: usize,
+        value: f64,
+    },
+    //a genome with a different number of objectives than the first one
+    ObjectiveLength {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+    //a setting outside the range it supports
+    InvalidConfig(String),
+    //a genome that broke its invariants, with the validation report
+    InvalidGenome(String),
+    //text that is not a serialized genome or dataset
+    Parse(String),
+    //file that could not be read or written
+    Io(String),
+    //evaluation that could not be completed by any worker
+    Network(String),
+}
+
+impl fmt::Display for NeatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NeatError::SelfLoop(node) => write!(f, "edge from node {} to itself", node),
+            NeatError::NodeOutOfRange { node, nodes } => {
+                write!(
+                    f,
+                    "node {} out of range for genome with {} nodes",
+                    node, nodes
+                )
+            }
+            NeatError::UnknownNode(node) => write!(f, "unknown node {}", node),
+            NeatError::InputLength { expected, found } => {
+                write!(f, "expected {} inputs but found {}", expected, found)
+            }
+            NeatError::FitnessLength { expected, found } => {
+                write!(
+                    f,
+                    "expected {} fitness values but found {}",
+                    expected, found
+                )
+            }
+            NeatError::TargetLength { expected, found } => {
+                write!(f, "expected {} targets but found {}", expected, found)
+            }
+            NeatError::InvalidFitness { index, value } => {
+                write!(f, "invalid fitness {} for genome {}", value, index)
+            }
+            NeatError::ObjectiveLength {
+                index,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "expected {} objectives for genome {} but found {}",
+                    expected, index, found
+                )
+            }
+            NeatError::InvalidConfig(reason) => write!(f, "invalid configuration: {}", reason),
+            NeatError::InvalidGenome(report) => write!(f, "invalid genome: {}", report),
+            NeatError::Parse(reason) => write!(f, "could not parse: {}", reason),
+            NeatError::Io(reason) => write!(f, "io error: {}", reason),
+            NeatError::Network(reason) => write!(f, "distributed evaluation failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for NeatError {}
@@ -0,0 +1,303 @@
+use crate::constants::*;
+use crate::error::NeatError;
+use crate::genome::Genome;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct QuadPoint {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub level: i32,
+    pub weight: f64,
+    pub children: Vec<QuadPoint>,
+}
+
+pub struct EsConnection {
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub weight: f64,
+}
+
+pub struct EsHyperNeat {
+    //substrate positions of the input and output nodes in [-1, 1] x [-1, 1]
+    pub inputs: Vec<(f64, f64)>,
+    pub outputs: Vec<(f64, f64)>,
+    //the quadtree is always divided down to initial_depth and never past max_depth
+    pub initial_depth: i32,
+    pub max_depth: i32,
+    //squares whose weights vary more than this are divided further
+    pub division_threshold: f64,
+    //squares whose weights vary less than this are considered for connections
+    pub variance_threshold: f64,
+    //a point is only connected if it differs this much from its neighbours
+    pub band_threshold: f64,
+    //rounds of hidden to hidden exploration
+    pub iteration_level: i32,
+    pub max_weight: f64,
+    pub act: fn(f64) -> f64,
+}
+
+impl QuadPoint {
+    pub fn new(x: f64, y: f64, width: f64, level: i32) -> Self {
+        Self {
+            x: x,
+            y: y,
+            width: width,
+            level: level,
+            weight: 0.0,
+            children: vec![],
+        }
+    }
+
+    pub fn leaf_weights(&self, weights: &mut Vec<f64>) {
+        for c in &self.children {
+            if c.children.is_empty() {
+                weights.push(c.weight);
+            } else {
+                c.leaf_weights(weights);
+            }
+        }
+    }
+
+    pub fn variance(&self) -> f64 {
+        //variance of the CPPN weights of all leaves below this square
+        let mut weights: Vec<f64> = vec![];
+        self.leaf_weights(&mut weights);
+        if weights.is_empty() {
+            return 0.0;
+        }
+        let n = weights.len() as f64;
+        let mean: f64 = weights.iter().sum::<f64>() / n;
+        weights.iter().map(|w| (w - mean) * (w - mean)).sum::<f64>() / n
+    }
+}
+
+fn key(p: (f64, f64)) -> (i64, i64) {
+    //points found by the quadtree are compared on a fixed grid to avoid float noise
+    ((p.0 * 1e6).round() as i64, (p.1 * 1e6).round() as i64)
+}
+
+impl EsHyperNeat {
+    pub fn new(inputs: Vec<(f64, f64)>, outputs: Vec<(f64, f64)>, act: fn(f64) -> f64) -> Self {
+        Self {
+            inputs: inputs,
+            outputs: outputs,
+            initial_depth: INITIAL_DEPTH,
+            max_depth: MAX_DEPTH,
+            division_threshold: DIVISION_THRESHOLD,
+            variance_threshold: VARIANCE_THRESHOLD,
+            band_threshold: BAND_THRESHOLD,
+            iteration_level: ITERATION_LEVEL,
+            max_weight: SUBSTRATE_WEIGHT,
+            act: act,
+        }
+    }
+
+    pub fn cppn_inputs(&self) -> i32 {
+        4
+    }
+
+    pub fn cppn_outputs(&self) -> i32 {
+        1
+    }
+
+    pub fn query(&self, cppn: &Genome, a: (f64, f64), b: (f64, f64), outgoing: bool) -> f64 {
+        //CPPN weight of the connection a -> b, or b -> a when searching incoming connections
+        let input = if outgoing {
+            vec![a.0, a.1, b.0, b.1]
+        } else {
+            vec![b.0, b.1, a.0, a.1]
+        };
+        cppn.evaluate(&input)[0]
+    }
+
+    pub fn division_and_initialization(
+        &self,
+        cppn: &Genome,
+        a: (f64, f64),
+        outgoing: bool,
+    ) -> QuadPoint {
+        //Divide the substrate into squares until the CPPN weights inside them are uniform
+        let mut root = QuadPoint::new(0.0, 0.0, 1.0, 1);
+        self.divide(cppn, a, outgoing, &mut root);
+        root
+    }
+
+    fn divide(&self, cppn: &Genome, a: (f64, f64), outgoing: bool, p: &mut QuadPoint) {
+        let half = p.width / 2.0;
+        for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+            let mut c = QuadPoint::new(p.x + dx * half, p.y + dy * half, half, p.level + 1);
+            c.weight = self.query(cppn, a, (c.x, c.y), outgoing);
+            p.children.push(c);
+        }
+        let uniform = p.variance() <= self.division_threshold;
+        if p.level < self.initial_depth || (p.level < self.max_depth && !uniform) {
+            for c in &mut p.children {
+                self.divide(cppn, a, outgoing, c);
+            }
+        }
+    }
+
+    pub fn pruning_and_extraction(
+        &self,
+        cppn: &Genome,
+        a: (f64, f64),
+        p: &QuadPoint,
+        outgoing: bool,
+        connections: &mut Vec<EsConnection>,
+    ) {
+        //Walk the quadtree and connect a to the points lying in a band of high variation
+        for c in &p.children {
+            if c.variance() >= self.variance_threshold {
+                self.pruning_and_extraction(cppn, a, c, outgoing, connections);
+                continue;
+            }
+            let neighbour = |x: f64, y: f64| -> f64 {
+                f64::abs(c.weight - self.query(cppn, a, (x, y), outgoing))
+            };
+            let left = neighbour(c.x - p.width, c.y);
+            let right = neighbour(c.x + p.width, c.y);
+            let top = neighbour(c.x, c.y - p.width);
+            let bottom = neighbour(c.x, c.y + p.width);
+            let band = f64::max(f64::min(top, bottom), f64::min(left, right));
+            if band > self.band_threshold {
+                let (from, to) = if outgoing {
+                    (a, (c.x, c.y))
+                } else {
+                    ((c.x, c.y), a)
+                };
+                connections.push(EsConnection {
+                    from: from,
+                    to: to,
+                    weight: c.weight,
+                });
+            }
+        }
+    }
+
+    fn search(&self, cppn: &Genome, a: (f64, f64), outgoing: bool) -> Vec<EsConnection> {
+        let root = self.division_and_initialization(cppn, a, outgoing);
+        let mut connections: Vec<EsConnection> = vec![];
+        self.pruning_and_extraction(cppn, a, &root, outgoing, &mut connections);
+        connections
+    }
+
+    pub fn connections(&self, cppn: &Genome) -> Vec<EsConnection> {
+        //Discover hidden nodes from the inputs, explore from them and then
+        //connect the outputs back to the discovered nodes
+        let inputs: BTreeSet<(i64, i64)> = self.inputs.iter().map(|p| key(*p)).collect();
+        let outputs: BTreeSet<(i64, i64)> = self.outputs.iter().map(|p| key(*p)).collect();
+        let mut hidden: BTreeSet<(i64, i64)> = BTreeSet::new();
+        let mut connections: Vec<EsConnection> = vec![];
+        let mut unexplored: Vec<(f64, f64)> = self.inputs.clone();
+        for _ in 0..=self.iteration_level {
+            let mut discovered: Vec<(f64, f64)> = vec![];
+            for a in &unexplored {
+                for c in self.search(cppn, *a, true) {
+                    let k = key(c.to);
+                    if inputs.contains(&k) {
+                        continue;
+                    }
+                    if !outputs.contains(&k) && hidden.insert(k) {
+                        discovered.push(c.to);
+                    }
+                    connections.push(c);
+                }
+            }
+            unexplored = discovered;
+        }
+        for a in &self.outputs {
+            for c in self.search(cppn, *a, false) {
+                if hidden.contains(&key(c.from)) {
+                    connections.push(c);
+                }
+            }
+        }
+        connections
+    }
+
+    pub fn build(&self, cppn: &Genome) -> Genome {
+        match self.try_build(cppn) {
+            Ok(network) => network,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_build(&self, cppn: &Genome) -> Result<Genome, NeatError> {
+        //Turn the discovered connections into a feed-forward network keeping only
+        //hidden nodes that lie on a path from an input to an output
+        if cppn.input_nodes != self.cppn_inputs() || cppn.output_nodes < self.cppn_outputs() {
+            return Err(NeatError::InvalidGenome(format!(
+                "cppn has {} inputs and {} outputs, the substrate needs {} inputs and at least {} outputs",
+                cppn.input_nodes,
+                cppn.output_nodes,
+                self.cppn_inputs(),
+                self.cppn_outputs()
+            )));
+        }
+        let connections = self.connections(cppn);
+        let mut forward: BTreeMap<(i64, i64), Vec<(i64, i64)>> = BTreeMap::new();
+        let mut backward: BTreeMap<(i64, i64), Vec<(i64, i64)>> = BTreeMap::new();
+        for c in &connections {
+            forward.entry(key(c.from)).or_default().push(key(c.to));
+            backward.entry(key(c.to)).or_default().push(key(c.from));
+        }
+        let from_inputs = reachable(self.inputs.iter().map(|p| key(*p)).collect(), &forward);
+        let to_outputs = reachable(self.outputs.iter().map(|p| key(*p)).collect(), &backward);
+
+        let n_in = self.inputs.len() as i32;
+        let n_out = self.outputs.len() as i32;
+        let mut network = Genome::new(n_in, n_out, self.act);
+        let mut ids: BTreeMap<(i64, i64), i32> = BTreeMap::new();
+        for (i, p) in self.inputs.iter().enumerate() {
+            ids.insert(key(*p), i as i32);
+        }
+        for (j, p) in self.outputs.iter().enumerate() {
+            ids.insert(key(*p), n_in + 1 + j as i32);
+        }
+        let mut inno: i32 = 0;
+        for c in &connections {
+            let (u, v) = (key(c.from), key(c.to));
+            if !from_inputs.contains(&u) || !to_outputs.contains(&v) {
+                continue;
+            }
+            for k in [u, v] {
+                if let Entry::Vacant(e) = ids.entry(k) {
+                    e.insert(network.add_node(network.num_nodes));
+                }
+            }
+            let (u, v) = (ids[&u], ids[&v]);
+            if u == v || network.edge_exist(u, v) {
+                continue;
+            }
+            let weight = c.weight.clamp(-1.0, 1.0) * self.max_weight;
+            network.add_edge(u, v, inno, weight, true);
+            //hidden to hidden connections may close a loop which can't be evaluated
+            if network.check_cycle() {
+                network.rm_last(u, v);
+                continue;
+            }
+            inno += 1;
+        }
+        Ok(network)
+    }
+}
+
+fn reachable(
+    start: Vec<(i64, i64)>,
+    adj: &BTreeMap<(i64, i64), Vec<(i64, i64)>>,
+) -> BTreeSet<(i64, i64)> {
+    let mut seen: BTreeSet<(i64, i64)> = start.iter().cloned().collect();
+    let mut stack = start;
+    while let Some(u) = stack.pop() {
+        if let Some(next) = adj.get(&u) {
+            for v in next {
+                if seen.insert(*v) {
+                    stack.push(*v);
+                }
+            }
+        }
+    }
+    seen
+}
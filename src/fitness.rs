@@ -0,0 +1,72 @@
+use crate::error::NeatError;
+
+pub enum FitnessPolicy {
+    //fail on NaN, infinite or negative fitness
+    Reject,
+    //NaN and values below the minimum become the minimum,
+    //infinity becomes the largest finite fitness. The minimum can't be negative
+    Clamp(f64),
+    //NaN and infinities are replaced by the smallest/largest finite fitness,
+    //then everything is shifted so the least fit genome has fitness 0
+    Shift,
+}
+
+impl FitnessPolicy {
+    pub fn apply(&self, fitness: &mut [f64]) -> Result<(), NeatError> {
+        //Make fitness safe for sharing and proportional offspring allocation
+        let mut lowest: f64 = f64::INFINITY;
+        let mut highest: f64 = f64::NEG_INFINITY;
+        for f in fitness.iter() {
+            if f.is_finite() {
+                lowest = f64::min(lowest, *f);
+                highest = f64::max(highest, *f);
+            }
+        }
+        match self {
+            FitnessPolicy::Reject => {
+                for (i, f) in fitness.iter().enumerate() {
+                    if !f.is_finite() || *f < 0.0 {
+                        return Err(NeatError::InvalidFitness {
+                            index: i,
+                            value: *f,
+                        });
+                    }
+                }
+            }
+            FitnessPolicy::Clamp(minimum) => {
+                if minimum.is_nan() || *minimum < 0.0 {
+                    return Err(NeatError::InvalidConfig(format!(
+                        "clamp minimum {} is negative",
+                        minimum
+                    )));
+                }
+                let highest = f64::max(highest, *minimum);
+                for f in fitness.iter_mut() {
+                    if *f == f64::INFINITY {
+                        *f = highest;
+                    } else if f.is_nan() || *f < *minimum {
+                        *f = *minimum;
+                    }
+                }
+            }
+            FitnessPolicy::Shift => {
+                if lowest > highest {
+                    //no finite values at all so every genome is equally fit
+                    lowest = 0.0;
+                    highest = 0.0;
+                }
+                for f in fitness.iter_mut() {
+                    if *f == f64::INFINITY {
+                        *f = highest;
+                    } else if !f.is_finite() {
+                        *f = lowest;
+                    }
+                    if lowest < 0.0 {
+                        *f -= lowest;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 use crate::constants::MAX_WEIGHT;
+use crate::error::NeatError;
 use crate::helper::{pertube, rand_f64, rand_i32};
 use crate::node::Node;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
 use std::rc::Rc;
 
 pub struct Genome {
@@ -11,7 +13,9 @@ pub struct Genome {
     pub hidden_nodes: i32,
     pub nodes: Vec<Rc<RefCell<Node>>>,
     pub num_nodes: i32,
+    //only active edges are counted, disabled genes are in total_connections
     pub num_connections: i32,
+    //active edges that can be split, bias edges are left out
     pub edges: BTreeSet<(i32, i32)>,
     pub act: fn(f64) -> f64,
 }
@@ -24,6 +28,22 @@ pub struct GenomeInfo {
     pub active: bool,
 }
 
+pub struct ValidationReport {
+    pub errors: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.errors.join("\n"))
+    }
+}
+
 impl Genome {
     pub fn new(input_nodes: i32, output_nodes: i32, act: fn(f64) -> f64) -> Self {
         //Initialize a Genome with no hidden nodes
@@ -51,6 +71,19 @@ impl Genome {
         output_nodes: i32,
         act: fn(f64) -> f64,
     ) -> Self {
+        match Genome::try_un_flatten(genes, input_nodes, output_nodes, act) {
+            Ok(g) => g,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_un_flatten(
+        genes: &[GenomeInfo],
+        input_nodes: i32,
+        output_nodes: i32,
+        act: fn(f64) -> f64,
+    ) -> Result<Self, NeatError> {
+        //Rebuild a genome from its genes, reporting genes that reference impossible nodes
         let mut base: Genome = Genome::new(input_nodes, output_nodes, act);
         let mut unique: BTreeSet<i32> = BTreeSet::new();
         let mut mapping: BTreeMap<i32, i32> = BTreeMap::new();
@@ -59,17 +92,22 @@ impl Genome {
             unique.insert(g.to);
         }
         for g in unique {
-            mapping.insert(g, mapping.len() as i32);
+            if g < 0 {
+                return Err(NeatError::UnknownNode(g));
+            }
+            //input, bias and output nodes always keep their position
             if g >= input_nodes + output_nodes + 1 {
-                base.add_node(g);
+                mapping.insert(g, base.add_node(g));
+            } else {
+                mapping.insert(g, g);
             }
         }
         for g in genes {
-            let u = *mapping.get(&g.from).unwrap();
-            let v = *mapping.get(&g.to).unwrap();
-            base.add_edge(u, v, g.innovation_number, g.weight, g.active);
+            let u = *mapping.get(&g.from).ok_or(NeatError::UnknownNode(g.from))?;
+            let v = *mapping.get(&g.to).ok_or(NeatError::UnknownNode(g.to))?;
+            base.try_add_edge(u, v, g.innovation_number, g.weight, g.active)?;
         }
-        return base;
+        Ok(base)
     }
 
     pub fn flatten(&self) -> Vec<GenomeInfo> {
@@ -89,6 +127,43 @@ impl Genome {
         genes
     }
 
+    pub fn to_text(&self) -> String {
+        //Single line of whitespace separated values: input and output counts followed by
+        //from, to, innovation, weight and active flag of every gene
+        let mut text = format!("{} {}", self.input_nodes, self.output_nodes);
+        for g in self.flatten() {
+            text += &format!(
+                " {} {} {} {} {}",
+                g.from, g.to, g.innovation_number, g.weight, g.active as i32
+            );
+        }
+        text
+    }
+
+    pub fn from_text(text: &str, act: fn(f64) -> f64) -> Result<Self, NeatError> {
+        //Inverse of to_text, the activation function isn't serialized
+        fn parse<T: std::str::FromStr>(token: Option<&str>) -> Result<T, NeatError> {
+            let token = token.ok_or(NeatError::Parse("unexpected end of input".to_string()))?;
+            token
+                .parse()
+                .map_err(|_| NeatError::Parse(format!("invalid value {}", token)))
+        }
+        let mut tokens = text.split_whitespace();
+        let input_nodes: i32 = parse(tokens.next())?;
+        let output_nodes: i32 = parse(tokens.next())?;
+        let mut genes: Vec<GenomeInfo> = vec![];
+        while let Some(from) = tokens.next() {
+            genes.push(GenomeInfo {
+                from: parse(Some(from))?,
+                to: parse(tokens.next())?,
+                innovation_number: parse(tokens.next())?,
+                weight: parse(tokens.next())?,
+                active: parse::<i32>(tokens.next())? != 0,
+            });
+        }
+        Genome::try_un_flatten(&genes, input_nodes, output_nodes, act)
+    }
+
     pub fn clone(&self) -> Self {
         //Implemented from flatten/unflatten functions
         Genome::un_flatten(
@@ -161,27 +236,74 @@ impl Genome {
             self.act,
         ))));
         self.num_nodes += 1;
+        self.hidden_nodes += 1;
         self.num_nodes - 1
     }
 
     pub fn add_edge(&mut self, from: i32, to: i32, inno_number: i32, weight: f64, active: bool) {
-        assert!(from != to);
-        if from != self.input_nodes {
-            //Since we wont split bias edges dont add to edge set
-            self.edges.insert((from, to));
+        if let Err(e) = self.try_add_edge(from, to, inno_number, weight, active) {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn try_add_edge(
+        &mut self,
+        from: i32,
+        to: i32,
+        inno_number: i32,
+        weight: f64,
+        active: bool,
+    ) -> Result<(), NeatError> {
+        if from == to {
+            return Err(NeatError::SelfLoop(from));
+        }
+        for node in [from, to] {
+            if node < 0 || node >= self.num_nodes {
+                return Err(NeatError::NodeOutOfRange {
+                    node: node,
+                    nodes: self.num_nodes,
+                });
+            }
+        }
+        if active {
+            if from != self.input_nodes {
+                //Since we wont split bias edges dont add to edge set
+                self.edges.insert((from, to));
+            }
+            self.num_connections += 1;
         }
-        self.num_connections += 1;
         self.nodes[from as usize].borrow_mut().add_edge(
             inno_number,
             weight,
             active,
             self.nodes[to as usize].clone(),
         );
+        Ok(())
+    }
+
+    pub fn complexity(&self) -> f64 {
+        //active connections plus hidden nodes, used to scale parsimony pressure
+        (self.num_connections + self.hidden_nodes) as f64
+    }
+
+    pub fn total_connections(&self) -> i32 {
+        //Every edge in the genome whether it is enabled or not
+        let mut total: i32 = 0;
+        for n in &self.nodes {
+            total += n.borrow().adj.len() as i32;
+        }
+        total
     }
 
     pub fn rm_last(&mut self, from: i32, to: i32) {
-        self.num_connections -= 1;
-        self.edges.remove(&(from, to));
+        let active = match self.nodes[from as usize].borrow().adj.last() {
+            Some(e) => e.active,
+            None => return,
+        };
+        if active {
+            self.num_connections -= 1;
+            self.edges.remove(&(from, to));
+        }
         self.nodes[from as usize].borrow_mut().del_back();
     }
 
@@ -194,12 +316,20 @@ impl Genome {
     }
 
     pub fn disable_edge(&mut self, from: i32, to: i32) {
+        if !self.nodes[from as usize].borrow().edge_active(to) {
+            return;
+        }
         self.edges.remove(&(from, to));
         self.num_connections -= 1;
         self.nodes[from as usize].borrow_mut().disable_edge(to);
     }
 
     pub fn enable_edge(&mut self, from: i32, to: i32) {
+        let node = self.nodes[from as usize].borrow();
+        if !node.edge_exist(to) || node.edge_active(to) {
+            return;
+        }
+        drop(node);
         //never add a bias edge to the edge set since it will try to split it
         if from != self.input_nodes {
             self.edges.insert((from, to));
@@ -265,6 +395,16 @@ impl Genome {
         return (-1, -1);
     }
 
+    pub fn try_evaluate(&self, input: &Vec<f64>) -> Result<Vec<f64>, NeatError> {
+        if input.len() != self.input_nodes as usize {
+            return Err(NeatError::InputLength {
+                expected: self.input_nodes as usize,
+                found: input.len(),
+            });
+        }
+        Ok(self.evaluate(input))
+    }
+
     pub fn evaluate(&self, input: &Vec<f64>) -> Vec<f64> {
         //Use topological sorting to evaluate outputs of the network
         //given an input vector
@@ -315,7 +455,7 @@ impl Genome {
             .to_vec();
     }
 
-    pub fn check_cycle(&mut self) -> bool {
+    pub fn check_cycle(&self) -> bool {
         //Returns if there exists a directed cycle in the graph
         let mut color: Vec<i32> = vec![0; self.num_nodes as usize];
         let mut q: Vec<i32> = vec![];
@@ -350,10 +490,134 @@ impl Genome {
         return false;
     }
 
+    pub fn validate(&self) -> ValidationReport {
+        //Check that the redundant bookkeeping agrees with the adjacency lists
+        let mut errors: Vec<String> = vec![];
+        let base_nodes = self.input_nodes + self.output_nodes + 1;
+        let total_nodes = self.nodes.len() as i32;
+        if self.num_nodes != total_nodes {
+            errors.push(format!(
+                "num_nodes is {} but the genome has {} nodes",
+                self.num_nodes, total_nodes
+            ));
+        }
+        if self.hidden_nodes != total_nodes - base_nodes {
+            errors.push(format!(
+                "hidden_nodes is {} but the genome has {} hidden nodes",
+                self.hidden_nodes,
+                total_nodes - base_nodes
+            ));
+        }
+        let mut global_ids: BTreeSet<i32> = BTreeSet::new();
+        let mut innovations: BTreeSet<i32> = BTreeSet::new();
+        let mut splittable: BTreeSet<(i32, i32)> = BTreeSet::new();
+        let mut connections: i32 = 0;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let n = node.borrow();
+            let u = i as i32;
+            if n.local_id != u {
+                errors.push(format!("node {} has local id {}", u, n.local_id));
+            }
+            if u < base_nodes && n.global_id != u {
+                errors.push(format!("node {} has global id {}", u, n.global_id));
+            }
+            if !global_ids.insert(n.global_id) {
+                errors.push(format!("global id {} is used twice", n.global_id));
+            }
+            let mut targets: BTreeSet<i32> = BTreeSet::new();
+            let mut active: i32 = 0;
+            let mut inactive: i32 = 0;
+            for e in &n.adj {
+                let v = e.to.borrow().local_id;
+                if v < 0 || v >= total_nodes || !Rc::ptr_eq(&e.to, &self.nodes[v as usize]) {
+                    errors.push(format!("edge {} -> {} leaves the genome", u, v));
+                    continue;
+                }
+                if u == v {
+                    errors.push(format!("edge {} -> {} is a self loop", u, v));
+                }
+                if !targets.insert(v) {
+                    errors.push(format!("edge {} -> {} appears twice", u, v));
+                }
+                if !innovations.insert(e.inno_number) {
+                    errors.push(format!("innovation number {} is used twice", e.inno_number));
+                }
+                if e.active {
+                    active += 1;
+                    if u != self.input_nodes {
+                        splittable.insert((u, v));
+                    }
+                } else {
+                    inactive += 1;
+                }
+            }
+            if n.active_edges != active || n.inactive_edges != inactive {
+                errors.push(format!(
+                    "node {} counts {} active and {} inactive edges but has {} and {}",
+                    u, n.active_edges, n.inactive_edges, active, inactive
+                ));
+            }
+            connections += active;
+        }
+        if self.num_connections != connections {
+            errors.push(format!(
+                "num_connections is {} but the genome has {} active edges",
+                self.num_connections, connections
+            ));
+        }
+        for e in self.edges.difference(&splittable) {
+            if e.0 == self.input_nodes {
+                errors.push(format!("bias edge {} -> {} is in the edge set", e.0, e.1));
+            } else {
+                errors.push(format!(
+                    "edge {} -> {} is in the edge set but not active",
+                    e.0, e.1
+                ));
+            }
+        }
+        for e in splittable.difference(&self.edges) {
+            errors.push(format!(
+                "active edge {} -> {} is missing from the edge set",
+                e.0, e.1
+            ));
+        }
+        if self.check_cycle() {
+            errors.push(String::from("active edges form a cycle"));
+        }
+        ValidationReport { errors: errors }
+    }
+
+    pub fn to_dot(&self) -> String {
+        //Graphviz description of the network, disabled edges are dashed
+        let mut dot = "digraph genome {\n    rankdir=LR;\n".to_string();
+        for (i, n) in self.nodes.iter().enumerate() {
+            let i = i as i32;
+            let shape = if i < self.input_nodes {
+                "box"
+            } else if i == self.input_nodes {
+                "diamond"
+            } else if i <= self.input_nodes + self.output_nodes {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            dot += &format!("    {} [shape={}];\n", n.borrow().global_id, shape);
+        }
+        for g in self.flatten() {
+            let style = if g.active { "solid" } else { "dashed" };
+            dot += &format!(
+                "    {} -> {} [label=\"{:.3}\", style={}];\n",
+                g.from, g.to, g.weight, style
+            );
+        }
+        dot + "}\n"
+    }
+
     pub fn network_info(&self) {
         println!(
-            "#Nodes {}, #Edges {}, #Active Connections {}",
+            "#Nodes {}, #Edges {}, #Active Edges {}, #Splittable Edges {}",
             self.num_nodes,
+            self.total_connections(),
             self.num_connections,
             self.edges.len(),
         );
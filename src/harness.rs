@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::Path;
+
+use crate::benchmarks::Benchmark;
+use crate::config::Config;
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::helper::seed;
+use crate::population::Population;
+
+pub struct Run {
+    pub seed: u64,
+    pub solved: bool,
+    //generations evolved before the champion solved the task, or the limit
+    pub generations: i32,
+    pub evaluations: i64,
+    //size of the final champion
+    pub nodes: i32,
+    pub connections: i32,
+    pub best_fitness: f64,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / f64::max(values.len() as f64, 1.0)
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    let m = mean(values);
+    let var: f64 = values.iter().map(|v| (v - m) * (v - m)).sum::<f64>();
+    f64::sqrt(var / f64::max(values.len() as f64, 1.0))
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => sorted[n / 2],
+        n => (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0,
+    }
+}
+
+pub struct Summary {
+    pub task: String,
+    pub runs: Vec<Run>,
+}
+
+impl Summary {
+    fn solved(&self, value: fn(&Run) -> f64) -> Vec<f64> {
+        self.runs.iter().filter(|r| r.solved).map(value).collect()
+    }
+
+    fn all(&self, value: fn(&Run) -> f64) -> Vec<f64> {
+        self.runs.iter().map(value).collect()
+    }
+
+    pub fn failure_rate(&self) -> f64 {
+        let failed = self.runs.iter().filter(|r| !r.solved).count();
+        failed as f64 / f64::max(self.runs.len() as f64, 1.0)
+    }
+
+    pub fn report(&self) -> String {
+        //Human readable statistics, generations and evaluations only count solved runs
+        let generations = self.solved(|r| r.generations as f64);
+        let evaluations = self.solved(|r| r.evaluations as f64);
+        let nodes = self.all(|r| r.nodes as f64);
+        let connections = self.all(|r| r.connections as f64);
+        let mut text = format!("task {}\n", self.task);
+        text += &format!("runs {}\n", self.runs.len());
+        text += &format!("failure rate {:.3}\n", self.failure_rate());
+        text += &format!(
+            "generations mean {:.2} std {:.2} median {:.1}\n",
+            mean(&generations),
+            std_dev(&generations),
+            median(&generations)
+        );
+        text += &format!(
+            "evaluations mean {:.1} std {:.1} median {:.1}\n",
+            mean(&evaluations),
+            std_dev(&evaluations),
+            median(&evaluations)
+        );
+        text += &format!(
+            "nodes mean {:.2} std {:.2}\n",
+            mean(&nodes),
+            std_dev(&nodes)
+        );
+        text += &format!(
+            "connections mean {:.2} std {:.2}\n",
+            mean(&connections),
+            std_dev(&connections)
+        );
+        text
+    }
+
+    pub fn csv(&self) -> String {
+        let mut text =
+            "seed,solved,generations,evaluations,nodes,connections,best_fitness\n".to_string();
+        for r in &self.runs {
+            text += &format!(
+                "{},{},{},{},{},{},{}\n",
+                r.seed,
+                r.solved as i32,
+                r.generations,
+                r.evaluations,
+                r.nodes,
+                r.connections,
+                r.best_fitness
+            );
+        }
+        text
+    }
+
+    pub fn write(&self, dir: &str) -> Result<(), NeatError> {
+        //Store runs.csv and summary.txt in the given directory
+        let io = |e: std::io::Error| NeatError::Io(format!("{}: {}", dir, e));
+        fs::create_dir_all(dir).map_err(io)?;
+        fs::write(Path::new(dir).join("runs.csv"), self.csv()).map_err(io)?;
+        fs::write(Path::new(dir).join("summary.txt"), self.report()).map_err(io)?;
+        Ok(())
+    }
+}
+
+pub struct Harness {
+    pub runs: usize,
+    //run i uses seed + i
+    pub seed: u64,
+    pub generations: i32,
+    pub population: i32,
+    pub act: fn(f64) -> f64,
+    //fresh configuration for every run
+    pub config: fn() -> Config,
+}
+
+impl Harness {
+    pub fn new(runs: usize, generations: i32, population: i32, act: fn(f64) -> f64) -> Self {
+        Self {
+            runs: runs,
+            seed: 0,
+            generations: generations,
+            population: population,
+            act: act,
+            config: Config::default,
+        }
+    }
+
+    pub fn run_once(&self, task: &Benchmark, run_seed: u64) -> Result<Run, NeatError> {
+        //Evolve until the champion solves the task or the generation limit is hit
+        seed(run_seed);
+        let mut pop = Population::with_config(
+            self.population,
+            task.inputs(),
+            task.outputs(),
+            self.act,
+            true,
+            (self.config)(),
+        );
+        let mut evaluations: i64 = 0;
+        let mut gen = 0;
+        loop {
+            let mut fitness = task.evaluate_population(&pop);
+            evaluations += fitness.len() as i64;
+            let best = fitness
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(idx, _)| idx)
+                .unwrap_or(0);
+            let champion: &Genome = &pop.population[best];
+            let solved = task.solved(champion);
+            if solved || gen >= self.generations {
+                return Ok(Run {
+                    seed: run_seed,
+                    solved: solved,
+                    generations: gen,
+                    evaluations: evaluations,
+                    nodes: champion.num_nodes,
+                    connections: champion.num_connections,
+                    best_fitness: fitness[best],
+                });
+            }
+            pop.try_next_generation(&mut fitness)?;
+            gen += 1;
+        }
+    }
+
+    pub fn run(&self, task: &Benchmark) -> Result<Summary, NeatError> {
+        let mut runs: Vec<Run> = vec![];
+        for i in 0..self.runs {
+            runs.push(self.run_once(task, self.seed + i as u64)?);
+        }
+        Ok(Summary {
+            task: task.name.clone(),
+            runs: runs,
+        })
+    }
+}
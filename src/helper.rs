@@ -1,10 +1,23 @@
-use rand::Rng;
+use std::cell::RefCell;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use crate::constants::{DELTA_PERMUTE, MAX_WEIGHT};
+
+thread_local! {
+    //every random draw of the crate goes through this generator so runs can be repeated
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn seed(seed: u64) {
+    //Make the random draws of the current thread reproducible
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
 pub fn chance(p: f64) -> bool {
     //given a probability returns 1 with probability p or 0 with probability 1-p
-    let mut rng = rand::thread_rng();
-    let x: f64 = rng.gen();
+    let x: f64 = RNG.with(|rng| rng.borrow_mut().gen());
     if x < p {
         return true;
     }
@@ -12,14 +25,12 @@ pub fn chance(p: f64) -> bool {
 }
 
 pub fn rand_i32(a: i32, b: i32) -> i32 {
-    let mut rng = rand::thread_rng();
-    let x: i32 = rng.gen_range(a..=b);
+    let x: i32 = RNG.with(|rng| rng.borrow_mut().gen_range(a..=b));
     return x;
 }
 
 pub fn rand_f64(a: f64, b: f64) -> f64 {
-    let mut rng = rand::thread_rng();
-    let x: f64 = rng.gen_range(a..=b);
+    let x: f64 = RNG.with(|rng| rng.borrow_mut().gen_range(a..=b));
     return x;
 }
 
@@ -29,3 +40,11 @@ pub fn pertube(mut x: f64) -> f64 {
     x = f64::max(x, -MAX_WEIGHT);
     return x;
 }
+
+pub fn shuffle<T>(v: &mut [T]) {
+    //uniformly random permutation in place
+    for i in (1..v.len()).rev() {
+        let j = rand_i32(0, i as i32) as usize;
+        v.swap(i, j);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::constants::{SUBSTRATE_WEIGHT, WEIGHT_THRESHOLD};
+use crate::error::NeatError;
+use crate::genome::Genome;
+
+pub struct Substrate {
+    //node coordinates grouped by layer, the first layer holds the inputs
+    //and the last the outputs, every coordinate has the same dimension
+    pub layers: Vec<Vec<Vec<f64>>>,
+}
+
+//The CPPN is an ordinary Genome, so all of its nodes share the single activation
+//function the population was created with. Mixing sine, gaussian and sigmoid nodes
+//the way CPPNs usually do is not supported, pick one activation (e.g. tanh) for all
+pub struct HyperNeat {
+    pub substrate: Substrate,
+    //CPPN outputs with a smaller magnitude don't produce a connection
+    pub weight_threshold: f64,
+    //expressed weights are scaled into [-max_weight, max_weight]
+    pub max_weight: f64,
+    //use a second CPPN output to decide if a connection is expressed (LEO),
+    //positive values express the connection so the CPPN should use tanh
+    pub leo: bool,
+    //activation function of the phenotype network
+    pub act: fn(f64) -> f64,
+}
+
+pub fn spread(n: usize) -> Vec<f64> {
+    //n evenly spaced positions in [-1, 1]
+    if n == 1 {
+        return vec![0.0];
+    }
+    (0..n)
+        .map(|i| -1.0 + 2.0 * (i as f64) / ((n - 1) as f64))
+        .collect()
+}
+
+impl Substrate {
+    pub fn new(layers: Vec<Vec<Vec<f64>>>) -> Self {
+        match Substrate::try_new(layers) {
+            Ok(s) => s,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_new(layers: Vec<Vec<Vec<f64>>>) -> Result<Self, NeatError> {
+        //Needs an input and an output layer, no empty layers and one coordinate dimension
+        if layers.len() < 2 {
+            return Err(NeatError::InvalidConfig(format!(
+                "substrate needs at least 2 layers but has {}",
+                layers.len()
+            )));
+        }
+        if let Some(l) = layers.iter().position(|layer| layer.is_empty()) {
+            return Err(NeatError::InvalidConfig(format!(
+                "substrate layer {} is empty",
+                l
+            )));
+        }
+        let dimension = layers[0][0].len();
+        for (l, layer) in layers.iter().enumerate() {
+            for point in layer {
+                if point.len() != dimension {
+                    return Err(NeatError::InvalidConfig(format!(
+                        "substrate point in layer {} has {} coordinates instead of {}",
+                        l,
+                        point.len(),
+                        dimension
+                    )));
+                }
+            }
+        }
+        Ok(Self { layers: layers })
+    }
+
+    pub fn layered(sizes: &[usize]) -> Self {
+        //2D substrate with each layer on a horizontal line, inputs at y = -1 and outputs at y = 1
+        let ys = spread(sizes.len());
+        let mut layers: Vec<Vec<Vec<f64>>> = vec![];
+        for (size, y) in sizes.iter().zip(ys) {
+            layers.push(spread(*size).iter().map(|x| vec![*x, y]).collect());
+        }
+        Substrate::new(layers)
+    }
+
+    pub fn grid(sizes: &[(usize, usize)]) -> Self {
+        //3D substrate with each layer a grid of width x height nodes stacked along z
+        let zs = spread(sizes.len());
+        let mut layers: Vec<Vec<Vec<f64>>> = vec![];
+        for (size, z) in sizes.iter().zip(zs) {
+            let mut layer: Vec<Vec<f64>> = vec![];
+            for y in spread(size.1) {
+                for x in spread(size.0) {
+                    layer.push(vec![x, y, z]);
+                }
+            }
+            layers.push(layer);
+        }
+        Substrate::new(layers)
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.layers[0][0].len()
+    }
+
+    pub fn inputs(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.layers[self.layers.len() - 1].len()
+    }
+}
+
+impl HyperNeat {
+    pub fn new(substrate: Substrate, act: fn(f64) -> f64) -> Self {
+        Self {
+            substrate: substrate,
+            weight_threshold: WEIGHT_THRESHOLD,
+            max_weight: SUBSTRATE_WEIGHT,
+            leo: false,
+            act: act,
+        }
+    }
+
+    pub fn cppn_inputs(&self) -> i32 {
+        //coordinates of the source node followed by the target node
+        (2 * self.substrate.dimension()) as i32
+    }
+
+    pub fn cppn_outputs(&self) -> i32 {
+        if self.leo {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn query(&self, cppn: &Genome, from: &[f64], to: &[f64]) -> Option<f64> {
+        //Weight of the connection between two substrate points, None if it isn't expressed
+        let mut input: Vec<f64> = from.to_vec();
+        input.extend_from_slice(to);
+        let out = cppn.evaluate(&input);
+        let w = out[0].clamp(-1.0, 1.0);
+        if self.leo {
+            if out[1] <= 0.0 {
+                return None;
+            }
+            return Some(w * self.max_weight);
+        }
+        if w.abs() <= self.weight_threshold {
+            return None;
+        }
+        //rescale so the weakest expressed connection starts from zero
+        let scaled = (w.abs() - self.weight_threshold) / (1.0 - self.weight_threshold);
+        Some(w.signum() * scaled * self.max_weight)
+    }
+
+    pub fn build(&self, cppn: &Genome) -> Genome {
+        match self.try_build(cppn) {
+            Ok(network) => network,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_build(&self, cppn: &Genome) -> Result<Genome, NeatError> {
+        //Query the CPPN for every pair of nodes in consecutive layers and
+        //return the resulting feed-forward network
+        if cppn.input_nodes != self.cppn_inputs() || cppn.output_nodes < self.cppn_outputs() {
+            return Err(NeatError::InvalidGenome(format!(
+                "cppn has {} inputs and {} outputs, the substrate needs {} inputs and at least {} outputs",
+                cppn.input_nodes,
+                cppn.output_nodes,
+                self.cppn_inputs(),
+                self.cppn_outputs()
+            )));
+        }
+        let layers = &self.substrate.layers;
+        let inputs = self.substrate.inputs() as i32;
+        let outputs = self.substrate.outputs() as i32;
+        let mut network = Genome::new(inputs, outputs, self.act);
+        //local ids of the substrate nodes, inputs then outputs keep the genome layout
+        let mut ids: Vec<Vec<i32>> = vec![(0..inputs).collect()];
+        for layer in &layers[1..layers.len() - 1] {
+            let mut hidden: Vec<i32> = vec![];
+            for _ in layer {
+                hidden.push(network.add_node(network.num_nodes));
+            }
+            ids.push(hidden);
+        }
+        ids.push((0..outputs).map(|j| inputs + 1 + j).collect());
+        let mut inno: i32 = 0;
+        for l in 0..layers.len() - 1 {
+            for (u, from) in ids[l].iter().zip(&layers[l]) {
+                for (v, to) in ids[l + 1].iter().zip(&layers[l + 1]) {
+                    if let Some(weight) = self.query(cppn, from, to) {
+                        network.add_edge(*u, *v, inno, weight, true);
+                        inno += 1;
+                    }
+                }
+            }
+        }
+        Ok(network)
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+
+use crate::error::NeatError;
+use crate::genome::{Genome, GenomeInfo};
+use crate::population::Population;
+
+pub enum Topology {
+    //island i only sends migrants to island i + 1
+    Ring,
+    //every island sends migrants to every other island
+    FullyConnected,
+}
+
+impl Topology {
+    pub fn neighbours(&self, island: usize, islands: usize) -> Vec<usize> {
+        match self {
+            Topology::Ring if islands > 1 => vec![(island + 1) % islands],
+            Topology::Ring => vec![],
+            Topology::FullyConnected => (0..islands).filter(|i| *i != island).collect(),
+        }
+    }
+}
+
+fn remap_node(
+    node: i32,
+    splits: &BTreeMap<i32, (i32, i32)>,
+    base: i32,
+    to: &mut Population,
+) -> Result<i32, NeatError> {
+    //a hidden node is identified by the edge it split, which is remapped first
+    if node < base {
+        return Ok(node);
+    }
+    let (u, v) = *splits.get(&node).ok_or(NeatError::UnknownNode(node))?;
+    let u = remap_node(u, splits, base, to)?;
+    let v = remap_node(v, splits, base, to)?;
+    Ok(to.get_inno_split(u, v))
+}
+
+pub fn remap(genome: &Genome, from: &Population, to: &mut Population) -> Result<Genome, NeatError> {
+    //Translate a genome's hidden node ids and innovation numbers from the tracker of one
+    //population to another, new structures are registered in the receiving tracker
+    let splits: BTreeMap<i32, (i32, i32)> = from.inno_split.iter().map(|(k, v)| (*v, *k)).collect();
+    let base = genome.input_nodes + genome.output_nodes + 1;
+    let mut genes: Vec<GenomeInfo> = vec![];
+    for g in genome.flatten() {
+        let u = remap_node(g.from, &splits, base, to)?;
+        let v = remap_node(g.to, &splits, base, to)?;
+        genes.push(GenomeInfo {
+            from: u,
+            to: v,
+            innovation_number: to.get_inno_edge(u, v),
+            weight: g.weight,
+            active: g.active,
+        });
+    }
+    genes.sort_by_key(|g| g.innovation_number);
+    Genome::try_un_flatten(&genes, genome.input_nodes, genome.output_nodes, genome.act)
+}
+
+//The innovation tracker of a population, islands sharing innovations move this single
+//tracker into whichever island is reproducing
+pub struct Innovations {
+    pub inno_count: i32,
+    pub unique_nodes: i32,
+    pub inno_split: BTreeMap<(i32, i32), i32>,
+    pub inno_edges: BTreeMap<(i32, i32), i32>,
+}
+
+impl Innovations {
+    pub fn take(p: &mut Population) -> Self {
+        Self {
+            inno_count: p.inno_count,
+            unique_nodes: p.unique_nodes,
+            inno_split: std::mem::take(&mut p.inno_split),
+            inno_edges: std::mem::take(&mut p.inno_edges),
+        }
+    }
+
+    pub fn lend(self, p: &mut Population) {
+        p.inno_count = self.inno_count;
+        p.unique_nodes = self.unique_nodes;
+        p.inno_split = self.inno_split;
+        p.inno_edges = self.inno_edges;
+    }
+}
+
+pub struct Islands {
+    pub islands: Vec<Population>,
+    pub topology: Topology,
+    //migration happens every interval generations
+    pub interval: i32,
+    //top genomes every island sends to each neighbour
+    pub migrants: usize,
+    //islands use a single innovation tracker instead of keeping their own,
+    //must be chosen before the first generation
+    pub shared_innovations: bool,
+    //the shared tracker between generations, taken from the first island when needed
+    pub innovations: Option<Innovations>,
+    pub gen: i32,
+}
+
+impl Islands {
+    pub fn new(islands: Vec<Population>, topology: Topology) -> Self {
+        Self {
+            islands: islands,
+            topology: topology,
+            interval: 5,
+            migrants: 2,
+            shared_innovations: false,
+            innovations: None,
+            gen: 0,
+        }
+    }
+
+    pub fn migrate(&mut self, fitness: &mut [Vec<f64>]) -> Result<(), NeatError> {
+        //Copy the top genomes of every island over the worst genomes of its neighbours,
+        //migrants keep their fitness so they compete in the receiving island right away
+        let n = self.islands.len();
+        let mut incoming: Vec<Vec<(f64, Genome)>> = (0..n).map(|_| vec![]).collect();
+        for i in 0..n {
+            let mut ranked: Vec<(f64, usize)> = fitness[i].iter().cloned().zip(0..).collect();
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+            for j in self.topology.neighbours(i, n) {
+                for (f, idx) in ranked.iter().take(self.migrants) {
+                    let genome = &self.islands[i].population[*idx];
+                    let migrant = if self.shared_innovations {
+                        genome.clone()
+                    } else {
+                        let (source, dest) = if i < j {
+                            let (a, b) = self.islands.split_at_mut(j);
+                            (&a[i], &mut b[0])
+                        } else {
+                            let (a, b) = self.islands.split_at_mut(i);
+                            (&b[0], &mut a[j])
+                        };
+                        remap(&source.population[*idx], source, dest)?
+                    };
+                    incoming[j].push((*f, migrant));
+                }
+            }
+        }
+        for (j, migrants) in incoming.into_iter().enumerate() {
+            let mut worst: Vec<(f64, usize)> = fitness[j].iter().cloned().zip(0..).collect();
+            worst.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            for ((_, idx), (f, migrant)) in worst.into_iter().zip(migrants) {
+                self.islands[j].population[idx] = migrant;
+                fitness[j][idx] = f;
+                self.islands[j].place_genome(idx as i32);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn next_generation(&mut self, fitness: &mut [Vec<f64>]) {
+        if let Err(e) = self.try_next_generation(fitness) {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn try_next_generation(&mut self, fitness: &mut [Vec<f64>]) -> Result<(), NeatError> {
+        //fitness holds one vector per island, migration runs before reproduction
+        if fitness.len() != self.islands.len() {
+            return Err(NeatError::FitnessLength {
+                expected: self.islands.len(),
+                found: fitness.len(),
+            });
+        }
+        for (p, f) in self.islands.iter().zip(fitness.iter()) {
+            if f.len() != p.population.len() {
+                return Err(NeatError::FitnessLength {
+                    expected: p.population.len(),
+                    found: f.len(),
+                });
+            }
+        }
+        if self.interval > 0 && self.gen > 0 && self.gen % self.interval == 0 {
+            self.migrate(fitness)?;
+        }
+        if self.shared_innovations && !self.islands.is_empty() {
+            let mut tracker = match self.innovations.take() {
+                Some(t) => t,
+                None => Innovations::take(&mut self.islands[0]),
+            };
+            let mut result: Result<(), NeatError> = Ok(());
+            for (p, f) in self.islands.iter_mut().zip(fitness.iter_mut()) {
+                tracker.lend(p);
+                result = p.try_next_generation(f);
+                tracker = Innovations::take(p);
+                if result.is_err() {
+                    break;
+                }
+            }
+            self.innovations = Some(tracker);
+            result?;
+        } else {
+            for (p, f) in self.islands.iter_mut().zip(fitness.iter_mut()) {
+                p.try_next_generation(f)?;
+            }
+        }
+        self.gen += 1;
+        Ok(())
+    }
+}
@@ -1,35 +1,46 @@
 pub mod activation;
+pub mod benchmarks;
+pub mod cli;
+pub mod coevolution;
+pub mod config;
 pub mod constants;
+pub mod cooperative;
+pub mod dataset;
+pub mod distributed;
+pub mod environments;
+pub mod episode;
+pub mod error;
+pub mod eshyperneat;
+pub mod fitness;
 pub mod genome;
+pub mod harness;
 pub mod helper;
+pub mod hyperneat;
+pub mod island;
 pub mod node;
+pub mod novelty;
+pub mod pareto;
 pub mod population;
+pub mod rtneat;
+pub mod selection;
+pub mod stats;
 use std::time::Instant;
+pub mod termination;
 pub mod test;
 use crate::activation::sigmoid;
+use crate::dataset::Loss;
+use crate::genome::Genome;
 use crate::population::Population;
-
-pub fn mse(inputs: &Vec<f64>, outputs: &Vec<f64>) -> f64 {
-    //return MSE of two vectors
-    assert_eq!(inputs.len(), outputs.len());
-    let mut error: f64 = 0.0;
-    for i in 0..inputs.len() {
-        //error += (inputs[i] - outputs[i]) * (inputs[i] - outputs[i]);
-        error += (inputs[i] - outputs[i]).abs();
-    }
-    error = error / (inputs.len() as f64);
-    //error = 1.0 / (f64::exp(error));
-    return error;
-}
+use crate::termination::{StopReason, Termination};
 
 pub fn metric(inputs: &Vec<f64>, outputs: &Vec<f64>) -> f64 {
     let xor: f64 = ((inputs[0] as i32) ^ (inputs[1] as i32)) as f64;
     let mut out: Vec<f64> = vec![];
     out.push(xor);
-    return mse(&out, &outputs);
+    return Loss::MeanAbsolute.error(&out, &outputs);
 }
 
-pub fn run_all(pop: &Population, stop: &mut bool) -> Vec<f64> {
+pub fn run_all(pop: &Population) -> Vec<f64> {
     let mut cummulative: Vec<f64> = vec![0.0; pop.population.len()];
     for i in 0..=1 {
         for j in 0..=1 {
@@ -71,30 +82,57 @@ pub fn run_all(pop: &Population, stop: &mut bool) -> Vec<f64> {
         }
     }
     println!("best fitness {} error {}", cummulative[max_idx], abs_error);
-    //pop.population[max_idx].network_info();
+    cummulative
+}
 
-    if abs_error <= 0.0001 {
-        pop.population[max_idx].network_info();
-        *stop = true;
+pub fn xor_error(genome: &Genome) -> f64 {
+    //sum of the absolute error over the 4 XOR cases
+    let mut abs_error: f64 = 0.0;
+    for i in 0..=1 {
+        for j in 0..=1 {
+            let outs = genome.evaluate(&vec![i as f64, j as f64]);
+            abs_error += f64::abs((i ^ j) as f64 - outs[0]);
+        }
     }
-    cummulative
+    abs_error
 }
 
 fn main() {
-    let mut p1: Population = Population::new(150, 2, 1, sigmoid, true);
-    for i in 0..80 {
-        let start = Instant::now(); // Record the starting time
-        let mut stop: bool = false;
-        let mut outs = run_all(&p1, &mut stop);
-        println!("iteration {} unique nodes {}", i, p1.unique_nodes);
-        if stop {
-            println!("Found Optimal Solution After {} generations", i);
-            return;
+    //with arguments run the command line, otherwise the XOR demo
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
-        p1.next_generation(&mut outs);
-        println!("Elapsed time: {} milliseconds", start.elapsed().as_millis());
-        p1.population_info();
-        println!("---------------------------------------------------------");
+        return;
+    }
+    let mut p1: Population = Population::new(150, 2, 1, sigmoid, true);
+    let criteria = Termination::new()
+        .custom("solved", |p| {
+            p.generation_best
+                .as_ref()
+                .is_some_and(|g| xor_error(g) <= 0.0001)
+        })
+        .max_generations(80);
+    let mut start = Instant::now();
+    let outcome = p1.run(
+        |pop| {
+            if pop.gen > 0 {
+                println!("Elapsed time: {} milliseconds", start.elapsed().as_millis());
+                pop.population_info();
+                println!("---------------------------------------------------------");
+            }
+            start = Instant::now();
+            println!("iteration {} unique nodes {}", pop.gen, pop.unique_nodes);
+            run_all(pop)
+        },
+        &criteria,
+    );
+    if outcome.reason == StopReason::MaxGenerations {
+        println!("NO OPTIMAL SOLUTION FOUND");
+        return;
     }
-    println!("NO OPTIMAL SOLUTION FOUND");
+    outcome.champion.network_info();
+    println!("Found Optimal Solution After {} generations", p1.gen);
 }
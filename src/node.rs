@@ -44,6 +44,11 @@ impl Node {
     }
 
     pub fn add_edge(&mut self, inno_number: i32, weight: f64, active: bool, to: Rc<RefCell<Node>>) {
+        if active {
+            self.active_edges += 1;
+        } else {
+            self.inactive_edges += 1;
+        }
         self.adj
             .push(Connection::new(inno_number, weight, active, to));
     }
@@ -51,8 +56,10 @@ impl Node {
     pub fn disable_edge(&mut self, to: i32) {
         for e in &mut self.adj {
             let v = e.to.borrow_mut();
-            if v.local_id == to {
+            if v.local_id == to && e.active {
                 e.active = false;
+                self.active_edges -= 1;
+                self.inactive_edges += 1;
             }
         }
     }
@@ -60,12 +67,23 @@ impl Node {
     pub fn enable_edge(&mut self, to: i32) {
         for e in &mut self.adj {
             let v = e.to.borrow_mut();
-            if v.local_id == to {
+            if v.local_id == to && !e.active {
                 e.active = true;
+                self.active_edges += 1;
+                self.inactive_edges -= 1;
             }
         }
     }
 
+    pub fn edge_active(&self, to: i32) -> bool {
+        for e in &self.adj {
+            if e.to.borrow().local_id == to {
+                return e.active;
+            }
+        }
+        return false;
+    }
+
     pub fn edge_weight(&self, to: i32) -> f64 {
         for e in &self.adj {
             let v = e.to.borrow_mut();
@@ -77,7 +95,13 @@ impl Node {
     }
 
     pub fn del_back(&mut self) {
-        self.adj.pop();
+        if let Some(e) = self.adj.pop() {
+            if e.active {
+                self.active_edges -= 1;
+            } else {
+                self.inactive_edges -= 1;
+            }
+        }
     }
 }
 
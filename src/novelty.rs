@@ -0,0 +1,40 @@
+pub fn distance(a: &[f64], b: &[f64]) -> f64 {
+    //euclidean distance between two behavior characterizations
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f64>()
+        .sqrt()
+}
+
+pub fn novelty(idx: usize, behaviors: &[Vec<f64>], archive: &[Vec<f64>], neighbours: usize) -> f64 {
+    //mean distance to the k nearest behaviors in the population and the archive
+    let mut dist: Vec<f64> = vec![];
+    for (i, b) in behaviors.iter().enumerate() {
+        if i != idx {
+            dist.push(distance(&behaviors[idx], b));
+        }
+    }
+    for b in archive {
+        dist.push(distance(&behaviors[idx], b));
+    }
+    if dist.is_empty() {
+        return 0.0;
+    }
+    dist.sort_by(|a, b| a.total_cmp(b));
+    let k = usize::clamp(neighbours, 1, dist.len());
+    dist[..k].iter().sum::<f64>() / (k as f64)
+}
+
+pub fn normalize(values: &[f64]) -> Vec<f64> {
+    //rescale into [0, 1] so novelty and fitness can be blended
+    let lowest = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let highest = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if highest - lowest <= 0.0 {
+        return vec![0.0; values.len()];
+    }
+    values
+        .iter()
+        .map(|v| (v - lowest) / (highest - lowest))
+        .collect()
+}
@@ -0,0 +1,90 @@
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    //a dominates b when it is at least as good in every objective and better in one,
+    //all objectives are maximized
+    let mut better: bool = false;
+    for (x, y) in a.iter().zip(b) {
+        if x < y {
+            return false;
+        }
+        if x > y {
+            better = true;
+        }
+    }
+    better
+}
+
+pub fn non_dominated_sort(objectives: &[Vec<f64>]) -> Vec<Vec<usize>> {
+    //Split the genomes into fronts, the first front is dominated by no genome
+    let n = objectives.len();
+    let mut dominated_by: Vec<i32> = vec![0; n];
+    let mut dominating: Vec<Vec<usize>> = vec![vec![]; n];
+    for i in 0..n {
+        for j in 0..n {
+            if dominates(&objectives[i], &objectives[j]) {
+                dominating[i].push(j);
+            } else if dominates(&objectives[j], &objectives[i]) {
+                dominated_by[i] += 1;
+            }
+        }
+    }
+    let mut fronts: Vec<Vec<usize>> = vec![];
+    let mut current: Vec<usize> = (0..n).filter(|i| dominated_by[*i] == 0).collect();
+    while !current.is_empty() {
+        let mut next: Vec<usize> = vec![];
+        for i in &current {
+            for j in &dominating[*i] {
+                dominated_by[*j] -= 1;
+                if dominated_by[*j] == 0 {
+                    next.push(*j);
+                }
+            }
+        }
+        fronts.push(current);
+        current = next;
+    }
+    fronts
+}
+
+fn column(objectives: &[Vec<f64>], front: &[usize], m: usize) -> Vec<f64> {
+    front.iter().map(|i| objectives[*i][m]).collect()
+}
+
+pub fn crowding_distance(objectives: &[Vec<f64>], front: &[usize]) -> Vec<f64> {
+    //How isolated each member of a front is, boundary members are infinitely far
+    let mut distance: Vec<f64> = vec![0.0; front.len()];
+    if front.is_empty() {
+        return distance;
+    }
+    for m in 0..objectives[front[0]].len() {
+        let values: Vec<f64> = column(objectives, front, m);
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+        let lowest = values[order[0]];
+        let highest = values[order[order.len() - 1]];
+        distance[order[0]] = f64::INFINITY;
+        distance[order[order.len() - 1]] = f64::INFINITY;
+        if highest - lowest <= 0.0 {
+            continue;
+        }
+        for k in 1..order.len().saturating_sub(1) {
+            let gap = values[order[k + 1]] - values[order[k - 1]];
+            distance[order[k]] += gap / (highest - lowest);
+        }
+    }
+    distance
+}
+
+pub fn pareto_fitness(objectives: &[Vec<f64>]) -> Vec<f64> {
+    //Scalar fitness that orders genomes by front first and crowding distance second,
+    //every front is worth one more than the front behind it
+    let fronts = non_dominated_sort(objectives);
+    let mut fitness: Vec<f64> = vec![0.0; objectives.len()];
+    for (rank, front) in fronts.iter().enumerate() {
+        let crowding = crowding_distance(objectives, front);
+        for (i, c) in front.iter().zip(crowding) {
+            let bonus = if c.is_infinite() { 1.0 } else { c / (1.0 + c) };
+            fitness[*i] = (fronts.len() - rank) as f64 + 0.5 * bonus;
+        }
+    }
+    fitness
+}
@@ -1,20 +1,80 @@
+use crate::config::Config;
+use crate::error::NeatError;
 use crate::genome::Genome;
 use crate::helper::rand_f64;
+use crate::novelty::{normalize, novelty};
+use crate::pareto::pareto_fitness;
+use crate::stats::GenerationStats;
+use crate::termination::{Outcome, Progress, Termination};
 use crate::{constants::*, helper::chance, helper::rand_i32};
 use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
 
 pub struct Species {
+    pub id: i32,
+    pub created: i32,
+    pub representative: Genome,
     pub organisms: Vec<i32>,
     pub leader: i32,
+    pub age: i32,
+    pub best_fitness: f64,
+    pub last_improved: i32,
 }
 
 impl Species {
-    pub fn new() -> Self {
+    pub fn new(id: i32, gen: i32, representative: Genome) -> Self {
         return Self {
+            id: id,
+            created: gen,
+            representative: representative,
             organisms: vec![],
             leader: 0,
+            age: 0,
+            best_fitness: f64::NEG_INFINITY,
+            last_improved: gen,
         };
     }
+
+    pub fn duplicate(&self) -> Self {
+        Self {
+            id: self.id,
+            created: self.created,
+            representative: self.representative.clone(),
+            organisms: self.organisms.clone(),
+            leader: self.leader,
+            age: self.age,
+            best_fitness: self.best_fitness,
+            last_improved: self.last_improved,
+        }
+    }
+
+    pub fn carry_over(&self) -> Self {
+        //An empty species for the next generation that keeps its identity and history
+        Self {
+            id: self.id,
+            created: self.created,
+            representative: self.representative.clone(),
+            organisms: vec![],
+            leader: 0,
+            age: self.age + 1,
+            best_fitness: self.best_fitness,
+            last_improved: self.last_improved,
+        }
+    }
+}
+
+//State touched by a generation step, restored when the step fails
+struct Snapshot {
+    previous_gen: Vec<Species>,
+    next_species_id: i32,
+    best_fitness: f64,
+    last_improved: i32,
+    threshold: f64,
+    stats: usize,
+    inno_count: i32,
+    unique_nodes: i32,
+    inno_split: BTreeMap<(i32, i32), i32>,
+    inno_edges: BTreeMap<(i32, i32), i32>,
 }
 
 pub struct Population {
@@ -27,7 +87,14 @@ pub struct Population {
     pub outputs: i32,
     pub act: fn(f64) -> f64,
     pub previous_gen: Vec<Species>,
+    pub next_species_id: i32,
     pub gen: i32,
+    pub best_fitness: f64,
+    pub last_improved: i32,
+    pub threshold: f64,
+    pub stats: Vec<GenerationStats>,
+    pub archive: Vec<Vec<f64>>,
+    pub config: Config,
 }
 
 impl Population {
@@ -37,6 +104,17 @@ impl Population {
         outputs: i32,
         act: fn(f64) -> f64,
         connect_ends: bool,
+    ) -> Self {
+        Population::with_config(size, inputs, outputs, act, connect_ends, Config::default())
+    }
+
+    pub fn with_config(
+        size: i32,
+        inputs: i32,
+        outputs: i32,
+        act: fn(f64) -> f64,
+        connect_ends: bool,
+        config: Config,
     ) -> Self {
         let mut pop: Vec<Genome> = vec![];
         for _ in 0..size {
@@ -49,6 +127,13 @@ impl Population {
         let mut obj = Self {
             gen: 0,
             previous_gen: vec![],
+            next_species_id: 0,
+            best_fitness: f64::NEG_INFINITY,
+            last_improved: 0,
+            threshold: config.compatibility_threshold,
+            stats: vec![],
+            archive: vec![],
+            config: config,
             population: pop,
             inno_count: (inputs + 1) * outputs,
             unique_nodes: inputs + outputs + 1,
@@ -114,6 +199,13 @@ impl Population {
     }
 
     pub fn breed(&self, u: &Genome, v: &Genome) -> Genome {
+        match self.try_breed(u, v) {
+            Ok(g) => g,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_breed(&self, u: &Genome, v: &Genome) -> Result<Genome, NeatError> {
         //Assume u is the more fit parent
         //For matching genes randomley pick between both parents
         //Otherwise only chose the more fit parents genes
@@ -129,16 +221,25 @@ impl Population {
             unique.insert(g.to);
         }
         for g in unique {
-            mapping.insert(g, mapping.len() as i32);
+            if g < 0 {
+                return Err(NeatError::UnknownNode(g));
+            }
+            //input, bias and output nodes always keep their position
             if g >= self.inputs + 1 + self.outputs {
-                base.add_node(g);
+                mapping.insert(g, base.add_node(g));
+            } else {
+                mapping.insert(g, g);
             }
         }
         while i < genome_u.len() && j < genome_v.len() {
             let v1: i32 = genome_u[i].innovation_number;
             let v2: i32 = genome_v[j].innovation_number;
-            let u: i32 = *mapping.get(&genome_u[i].from).unwrap();
-            let v: i32 = *mapping.get(&genome_u[i].to).unwrap();
+            let u: i32 = *mapping
+                .get(&genome_u[i].from)
+                .ok_or(NeatError::UnknownNode(genome_u[i].from))?;
+            let v: i32 = *mapping
+                .get(&genome_u[i].to)
+                .ok_or(NeatError::UnknownNode(genome_u[i].to))?;
             if v1 == v2 {
                 let one_disabled: bool = !(genome_u[i].active && genome_v[j].active);
                 let mut active: bool;
@@ -153,13 +254,13 @@ impl Population {
                 if one_disabled && chance(0.75) {
                     active = false;
                 }
-                base.add_edge(u, v, v1, weight, active);
+                base.try_add_edge(u, v, v1, weight, active)?;
                 i += 1;
                 j += 1;
             } else if v1 < v2 {
                 let weight: f64 = genome_u[i].weight;
                 let active: bool = genome_u[i].active;
-                base.add_edge(u, v, v1, weight, active);
+                base.try_add_edge(u, v, v1, weight, active)?;
                 i += 1;
             } else {
                 j += 1;
@@ -167,15 +268,19 @@ impl Population {
         }
         while i < genome_u.len() {
             let v1: i32 = genome_u[i].innovation_number;
-            let u: i32 = *mapping.get(&genome_u[i].from).unwrap();
-            let v: i32 = *mapping.get(&genome_u[i].to).unwrap();
+            let u: i32 = *mapping
+                .get(&genome_u[i].from)
+                .ok_or(NeatError::UnknownNode(genome_u[i].from))?;
+            let v: i32 = *mapping
+                .get(&genome_u[i].to)
+                .ok_or(NeatError::UnknownNode(genome_u[i].to))?;
             let weight: f64 = genome_u[i].weight;
             let active: bool = genome_u[i].active;
-            base.add_edge(u, v, v1, weight, active);
+            base.try_add_edge(u, v, v1, weight, active)?;
             i += 1;
         }
         //we dont care about the excess genes from parent v
-        return base;
+        Ok(base)
     }
 
     pub fn evaluate_all(
@@ -193,6 +298,210 @@ impl Population {
         return fitness;
     }
 
+    pub fn checkpoint(&self) -> String {
+        //Text snapshot of the genomes and innovation tracker, species and their stagnation
+        //history are rebuilt on load so a resumed run doesn't retrace the original one
+        let mut text = format!(
+            "population {} {} {} {} {} {} {} {} {}\n",
+            self.inputs,
+            self.outputs,
+            self.gen,
+            self.inno_count,
+            self.unique_nodes,
+            self.threshold,
+            self.next_species_id,
+            self.best_fitness,
+            self.last_improved
+        );
+        for ((from, to), node) in &self.inno_split {
+            text += &format!("split {} {} {}\n", from, to, node);
+        }
+        for ((from, to), inno) in &self.inno_edges {
+            text += &format!("edge {} {} {}\n", from, to, inno);
+        }
+        for g in &self.population {
+            text += &format!("genome {}\n", g.to_text());
+        }
+        text
+    }
+
+    pub fn from_checkpoint(
+        text: &str,
+        act: fn(f64) -> f64,
+        config: Config,
+    ) -> Result<Self, NeatError> {
+        fn parse<T: std::str::FromStr>(token: Option<&str>) -> Result<T, NeatError> {
+            let token = token.ok_or(NeatError::Parse("truncated checkpoint".to_string()))?;
+            token
+                .parse()
+                .map_err(|_| NeatError::Parse(format!("invalid value {}", token)))
+        }
+        let mut pop: Option<Population> = None;
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let mut tokens = rest.split_whitespace();
+            if kind == "population" {
+                let mut p = Population::with_config(
+                    0,
+                    parse(tokens.next())?,
+                    parse(tokens.next())?,
+                    act,
+                    false,
+                    Config::default(),
+                );
+                p.gen = parse(tokens.next())?;
+                p.inno_count = parse(tokens.next())?;
+                p.unique_nodes = parse(tokens.next())?;
+                p.threshold = parse(tokens.next())?;
+                p.next_species_id = parse(tokens.next())?;
+                p.best_fitness = parse(tokens.next())?;
+                p.last_improved = parse(tokens.next())?;
+                pop = Some(p);
+                continue;
+            }
+            let p = pop.as_mut().ok_or(NeatError::Parse(
+                "checkpoint must start with population".to_string(),
+            ))?;
+            match kind {
+                "split" => {
+                    let key = (parse(tokens.next())?, parse(tokens.next())?);
+                    p.inno_split.insert(key, parse(tokens.next())?);
+                }
+                "edge" => {
+                    let key = (parse(tokens.next())?, parse(tokens.next())?);
+                    p.inno_edges.insert(key, parse(tokens.next())?);
+                }
+                "genome" => p.population.push(Genome::from_text(rest, act)?),
+                _ => return Err(NeatError::Parse(format!("unknown record {}", kind))),
+            }
+        }
+        let mut pop = pop.ok_or(NeatError::Parse("empty checkpoint".to_string()))?;
+        pop.config = config;
+        pop.assign_species();
+        Ok(pop)
+    }
+
+    pub fn evaluate_genomes(&self, evaluate: fn(&Genome) -> f64) -> Vec<f64> {
+        //Fitness of every individual from an evaluator that runs the whole genome
+        self.population.iter().map(evaluate).collect()
+    }
+
+    pub fn evaluate_behaviors(
+        &self,
+        inputs: &Vec<f64>,
+        behavior: fn(&Vec<f64>, &Vec<f64>) -> Vec<f64>,
+    ) -> Vec<Vec<f64>> {
+        //Given an input vector return the behavior characterization of each individual
+        let mut behaviors: Vec<Vec<f64>> = vec![];
+        for sp in &self.population {
+            behaviors.push(behavior(inputs, &sp.evaluate(inputs)));
+        }
+        behaviors
+    }
+
+    pub fn novelty_scores(&mut self, behaviors: &[Vec<f64>]) -> Vec<f64> {
+        //Novelty of every behavior against the population and the archive,
+        //sufficiently novel behaviors are then added to the archive
+        let mut scores: Vec<f64> = vec![];
+        for i in 0..behaviors.len() {
+            scores.push(novelty(
+                i,
+                behaviors,
+                &self.archive,
+                self.config.novelty_neighbours,
+            ));
+        }
+        for (i, s) in scores.iter().enumerate() {
+            if *s > self.config.archive_threshold {
+                self.archive.push(behaviors[i].clone());
+            }
+        }
+        scores
+    }
+
+    pub fn try_next_generation_novelty(
+        &mut self,
+        behaviors: &[Vec<f64>],
+        fitness: &[f64],
+    ) -> Result<(), NeatError> {
+        //Reproduce using novelty blended with fitness instead of the raw fitness
+        if behaviors.len() != self.population.len() {
+            return Err(NeatError::FitnessLength {
+                expected: self.population.len(),
+                found: behaviors.len(),
+            });
+        }
+        let w = self.config.novelty_weight;
+        let mut fitness: Vec<f64> = fitness.to_vec();
+        //fitness is only needed when it takes part in the blend
+        if w < 1.0 {
+            if fitness.len() != self.population.len() {
+                return Err(NeatError::FitnessLength {
+                    expected: self.population.len(),
+                    found: fitness.len(),
+                });
+            }
+            self.config.fitness_policy.apply(&mut fitness)?;
+        }
+        let archived = self.archive.len();
+        let mut blended: Vec<f64> = normalize(&self.novelty_scores(behaviors));
+        if w < 1.0 {
+            for (b, f) in blended.iter_mut().zip(normalize(&fitness)) {
+                *b = w * *b + (1.0 - w) * f;
+            }
+        }
+        let result = self.try_next_generation(&mut blended);
+        if result.is_err() {
+            //behaviors of a generation that never happened don't belong in the archive
+            self.archive.truncate(archived);
+        }
+        result
+    }
+
+    pub fn evaluate_objectives(
+        &self,
+        inputs: &Vec<f64>,
+        objectives: fn(&Vec<f64>, &Vec<f64>) -> Vec<f64>,
+    ) -> Vec<Vec<f64>> {
+        //Given an input vector return the objective values of each individual
+        self.evaluate_behaviors(inputs, objectives)
+    }
+
+    pub fn try_next_generation_multi(&mut self, objectives: &[Vec<f64>]) -> Result<(), NeatError> {
+        //Rank genomes by non-dominated sorting and crowding distance over all
+        //objectives (maximized) and reproduce using the rank as fitness
+        if objectives.len() != self.population.len() {
+            return Err(NeatError::FitnessLength {
+                expected: self.population.len(),
+                found: objectives.len(),
+            });
+        }
+        let mut ranked: Vec<Vec<f64>> = vec![];
+        for (i, o) in objectives.iter().enumerate() {
+            if o.len() != objectives[0].len() {
+                return Err(NeatError::ObjectiveLength {
+                    index: i,
+                    expected: objectives[0].len(),
+                    found: o.len(),
+                });
+            }
+            if let Some(v) = o.iter().find(|v| !v.is_finite()) {
+                return Err(NeatError::InvalidFitness {
+                    index: i,
+                    value: *v,
+                });
+            }
+            let mut o = o.clone();
+            if self.config.complexity_objective {
+                o.push(-(self.population[i].num_connections as f64));
+                o.push(-(self.population[i].num_nodes as f64));
+            }
+            ranked.push(o);
+        }
+        let mut fitness = pareto_fitness(&ranked);
+        self.try_next_generation(&mut fitness)
+    }
+
     pub fn delta(&self, u: &Genome, v: &Genome) -> f64 {
         //calculate combatability between two organisms
         let genome_u = u.flatten();
@@ -230,134 +539,338 @@ impl Population {
     }
 
     pub fn speciate(&self, new_gen: &Vec<Genome>) -> Vec<Species> {
+        //Place each genome in the first species whose representative is compatible with it
+        //Existing species keep their id, unmatched genomes start new species
         let mut sp: Vec<Species> = vec![];
-        let mut leaders: Vec<&Genome> = vec![];
-        for i in 0..self.previous_gen.len() {
-            if self.previous_gen[i].organisms.len() == 0 {
+        for s in &self.previous_gen {
+            if s.organisms.is_empty() {
                 continue;
             }
-            leaders.push(&self.population[self.previous_gen[i].leader as usize]);
-            sp.push(Species::new());
+            sp.push(s.carry_over());
         }
-        let mut idx: i32 = 0;
-        for p in new_gen {
+        let mut next_id: i32 = self.next_species_id;
+        for (idx, p) in new_gen.iter().enumerate() {
             let mut added: bool = false;
-            for i in 0..leaders.len() {
-                let d: f64 = self.delta(leaders[i], &p);
-                if d < DT {
+            for s in sp.iter_mut() {
+                if self.delta(&s.representative, p) < self.threshold {
                     added = true;
-                    sp[i].organisms.push(idx);
+                    s.organisms.push(idx as i32);
                     break;
                 }
             }
             if !added {
-                leaders.push(&new_gen[idx as usize]);
-                sp.push(Species::new());
-                let size: usize = sp.len();
-                sp[size - 1].organisms.push(idx);
+                let mut s = Species::new(next_id, self.gen, p.clone());
+                s.organisms.push(idx as i32);
+                sp.push(s);
+                next_id += 1;
             }
-            idx += 1;
         }
-        let mut new_species: Vec<Species> = vec![];
-        for i in 0..sp.len() {
-            if sp[i].organisms.len() == 0 {
-                continue;
+        //species that received no genomes have gone extinct
+        sp.retain(|s| !s.organisms.is_empty());
+        for s in &mut sp {
+            let u = rand_i32(1, s.organisms.len() as i32) - 1;
+            s.leader = s.organisms[u as usize];
+            s.representative = new_gen[s.leader as usize].clone();
+        }
+        sp
+    }
+
+    pub fn assign_species(&mut self) {
+        //Speciate the current population and keep species ids unique across generations
+        self.previous_gen = self.speciate(&self.population);
+        for s in &self.previous_gen {
+            self.next_species_id = i32::max(self.next_species_id, s.id + 1);
+        }
+    }
+
+    pub fn remove_from_species(&mut self, genome_idx: i32) {
+        //Take a genome out of its species, species left without members are dropped
+        for s in &mut self.previous_gen {
+            s.organisms.retain(|a| *a != genome_idx);
+            if s.leader == genome_idx && !s.organisms.is_empty() {
+                s.leader = s.organisms[0];
             }
-            new_species.push(Species::new());
-            for g in &sp[i].organisms {
-                let size: usize = new_species.len();
-                new_species[size - 1].organisms.push(*g);
+        }
+        self.previous_gen.retain(|s| !s.organisms.is_empty());
+    }
+
+    pub fn place_genome(&mut self, genome_idx: i32) {
+        //Move a single genome, usually a freshly replaced one, into the first compatible
+        //species or a new one without speciating the rest of the population
+        self.remove_from_species(genome_idx);
+        let genome = &self.population[genome_idx as usize];
+        match self.classify(genome) {
+            Some(id) => {
+                let s = self.previous_gen.iter_mut().find(|s| s.id == id).unwrap();
+                s.organisms.push(genome_idx);
+            }
+            None => {
+                let mut s = Species::new(self.next_species_id, self.gen, genome.clone());
+                s.organisms.push(genome_idx);
+                s.leader = genome_idx;
+                self.previous_gen.push(s);
+                self.next_species_id += 1;
             }
         }
-        for i in 0..new_species.len() {
-            let u = rand_i32(1, new_species[i].organisms.len() as i32) - 1;
-            let leader_idx = new_species[i].organisms[u as usize];
-            new_species[i].leader = leader_idx;
+    }
+
+    pub fn get_species(&self, id: i32) -> Option<&Species> {
+        self.previous_gen.iter().find(|s| s.id == id)
+    }
+
+    pub fn species_of(&self, genome_idx: i32) -> Option<i32> {
+        //Species id of a genome in the current population
+        self.previous_gen
+            .iter()
+            .find(|s| s.organisms.contains(&genome_idx))
+            .map(|s| s.id)
+    }
+
+    pub fn classify(&self, genome: &Genome) -> Option<i32> {
+        //Species id a genome from any generation would join, None if it would start a new one
+        self.previous_gen
+            .iter()
+            .find(|s| self.delta(&s.representative, genome) < self.threshold)
+            .map(|s| s.id)
+    }
+
+    pub fn ranked_members(&self, gen_idx: usize, fitness: &[f64]) -> Vec<(f64, i32)> {
+        //(fitness, organism) pairs of a species sorted from most to least fit
+        let mut best_ones: Vec<(f64, i32)> = vec![];
+        for a in &self.previous_gen[gen_idx].organisms {
+            best_ones.push((fitness[*a as usize], *a));
+        }
+        best_ones.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+        best_ones
+    }
+
+    pub fn interspecies_parent(&self, gen_idx: usize, fitness: &[f64]) -> Option<(f64, i32)> {
+        //pick a parent from a random other species with the configured selection strategy
+        let others: Vec<usize> = (0..self.previous_gen.len())
+            .filter(|i| *i != gen_idx && !self.previous_gen[*i].organisms.is_empty())
+            .collect();
+        if others.is_empty() {
+            return None;
         }
-        return sp;
+        let other = others[(rand_i32(1, others.len() as i32) - 1) as usize];
+        let ranked = self.ranked_members(other, fitness);
+        Some(ranked[self.config.selection.select(&ranked)])
     }
 
     pub fn create_species(
         &mut self,
         gen_idx: i32,
-        fitness: &Vec<f64>,
+        fitness: &[f64],
         number_offspring: i32,
-    ) -> Vec<Genome> {
-        //only use the highest performing members of each species to reproduce
-        //If the species has > 5 networks then keep the champion
-        let mut curr_gen: Vec<&Genome> = vec![];
-        for a in &self.previous_gen[gen_idx as usize].organisms {
-            curr_gen.push(&self.population[*a as usize]);
-        }
-        let mut best_ones: Vec<(f64, i32)> = vec![];
-        for i in 0..fitness.len() {
-            best_ones.push((fitness[i], i as i32));
-        }
-        best_ones.sort_by(|a, b| b.partial_cmp(a).unwrap());
-        let top_members: i32 = ((curr_gen.len() as f64) * TOP_ONES).ceil() as i32;
-        //println!("top members {}", top_members);
+    ) -> Result<Vec<Genome>, NeatError> {
+        //fitness holds the value of every organism in the population
+        //parents are chosen from the species with the configured selection strategy
+        //If the species is large enough then keep its champions
+        let best_ones = self.ranked_members(gen_idx as usize, fitness);
         let mut champion_flag: i32 = 0;
         let mut new_gen: Vec<Genome> = vec![];
-        if curr_gen.len() > 5 {
-            //champion_flag = ((curr_gen.len() as f64) * 0.1).floor() as i32;
-            champion_flag = 1;
+        if best_ones.len() as i32 >= self.config.elitism_min_size {
+            champion_flag = i32::min(self.config.elitism, best_ones.len() as i32);
             champion_flag = i32::min(champion_flag, number_offspring);
             for k in 0..champion_flag {
-                new_gen.push(curr_gen[best_ones[k as usize].1 as usize].clone())
+                new_gen.push(self.population[best_ones[k as usize].1 as usize].clone())
             }
         }
         let mut remaining_offspring = number_offspring - champion_flag;
         let mut only_mutate: i32 = ((remaining_offspring as f64) * NO_CROSSING).round() as i32;
         only_mutate = i32::min(remaining_offspring, only_mutate);
         for _ in 0..only_mutate {
-            let mut u = (rand_i32(1, top_members) - 1) as usize;
-            u = best_ones[u].1 as usize;
-            let offspring = curr_gen[u].clone();
+            let u = best_ones[self.config.selection.select(&best_ones)].1 as usize;
+            let offspring = self.population[u].clone();
             new_gen.push(offspring);
         }
         remaining_offspring -= only_mutate;
         for _ in 0..(remaining_offspring) {
-            let mut u = (rand_i32(1, top_members) - 1) as usize;
-            let mut v = (rand_i32(1, top_members) - 1) as usize;
-            if best_ones[u].0 < best_ones[v].0 {
-                let t = u;
-                u = v;
-                v = t;
-            }
-            u = best_ones[u].1 as usize;
-            v = best_ones[v].1 as usize;
-            let offspring = self.breed(&curr_gen[u], &curr_gen[v]);
+            let offspring = self.crossover(gen_idx as usize, &best_ones, fitness)?;
             new_gen.push(offspring);
         }
         for i in 0..new_gen.len() {
-            if champion_flag == 1 && i == 0 {
+            if (i as i32) < champion_flag {
                 continue;
             }
-            if chance(RANDOM_SPLIT) {
-                self.random_split(&mut new_gen[i]);
+            self.try_mutate(&mut new_gen[i])?;
+        }
+        Ok(new_gen)
+    }
+
+    pub fn crossover(
+        &self,
+        gen_idx: usize,
+        best_ones: &[(f64, i32)],
+        fitness: &[f64],
+    ) -> Result<Genome, NeatError> {
+        //Breed two parents picked from a species, occasionally mating with another species
+        let mut u = best_ones[self.config.selection.select(best_ones)];
+        let mut v = best_ones[self.config.selection.select(best_ones)];
+        if chance(self.config.interspecies_rate) {
+            if let Some(other) = self.interspecies_parent(gen_idx, fitness) {
+                v = other;
             }
-            if chance(RANDOM_EDGE) {
-                self.random_edge(&mut new_gen[i]);
+        }
+        if u.0 < v.0 {
+            std::mem::swap(&mut u, &mut v);
+        }
+        self.try_breed(
+            &self.population[u.1 as usize],
+            &self.population[v.1 as usize],
+        )
+    }
+
+    pub fn try_mutate(&mut self, genome: &mut Genome) -> Result<(), NeatError> {
+        if chance(RANDOM_SPLIT) {
+            self.random_split(genome);
+        }
+        if chance(RANDOM_EDGE) {
+            self.random_edge(genome);
+        }
+        if chance(MUTATE_EDGES) {
+            if chance(0.9) {
+                genome.permute_weights();
+            } else {
+                genome.new_weights();
             }
-            if chance(MUTATE_EDGES) {
-                if chance(0.9) {
-                    new_gen[i].permute_weights();
-                } else {
-                    new_gen[i].new_weights();
+        }
+        if self.config.validate_genomes {
+            let report = genome.validate();
+            if !report.is_valid() {
+                return Err(NeatError::InvalidGenome(report.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run<F: FnMut(&Population) -> Vec<f64>>(
+        &mut self,
+        evaluator: F,
+        criteria: &Termination,
+    ) -> Outcome {
+        match self.try_run(evaluator, criteria) {
+            Ok(outcome) => outcome,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    pub fn try_run<F: FnMut(&Population) -> Vec<f64>>(
+        &mut self,
+        mut evaluator: F,
+        criteria: &Termination,
+    ) -> Result<Outcome, NeatError> {
+        //Evaluate and advance generations until a termination criterion is met,
+        //the population is left at the generation that met it
+        if criteria.criteria.is_empty() {
+            return Err(NeatError::InvalidConfig(
+                "no termination criteria, the run would never stop".to_string(),
+            ));
+        }
+        let start = Instant::now();
+        let mut progress = Progress {
+            generations: 0,
+            evaluations: 0,
+            elapsed: Duration::ZERO,
+            best_fitness: f64::NEG_INFINITY,
+            champion: None,
+            last_improved: 0,
+            fitness: vec![],
+            generation_best: None,
+        };
+        loop {
+            let fitness = evaluator(self);
+            if fitness.len() != self.population.len() {
+                return Err(NeatError::FitnessLength {
+                    expected: self.population.len(),
+                    found: fitness.len(),
+                });
+            }
+            progress.generations += 1;
+            progress.evaluations += fitness.len() as i64;
+            for (i, f) in fitness.iter().enumerate() {
+                if *f > progress.best_fitness {
+                    progress.best_fitness = *f;
+                    progress.champion = Some(self.population[i].clone());
+                    progress.last_improved = progress.generations;
                 }
             }
+            progress.elapsed = start.elapsed();
+            progress.generation_best = fitness
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(idx, _)| self.population[idx].clone());
+            progress.fitness = fitness;
+            if let Some(reason) = criteria.check(&progress) {
+                let champion = match progress.champion {
+                    Some(c) => c,
+                    None => self.population[0].clone(),
+                };
+                return Ok(Outcome {
+                    champion: champion,
+                    best_fitness: progress.best_fitness,
+                    reason: reason,
+                    generations: progress.generations,
+                    evaluations: progress.evaluations,
+                });
+            }
+            let mut fitness = std::mem::take(&mut progress.fitness);
+            self.try_next_generation(&mut fitness)?;
         }
-        new_gen
     }
 
     pub fn next_generation(&mut self, fitness: &mut Vec<f64>) {
+        if let Err(e) = self.try_next_generation(fitness) {
+            panic!("{}", e);
+        }
+    }
+
+    pub fn try_next_generation(&mut self, fitness: &mut Vec<f64>) -> Result<(), NeatError> {
         //population stores the current generation with an input of fitness values
         //create a new gereration after specification
-        assert_eq!(fitness.len(), self.population.len());
+        let mut adjusted: Vec<f64> = self.check_fitness(fitness)?;
+        //Reproduction can still fail on a corrupted genome, so keep everything it
+        //mutates and put it back if it does
+        let saved = self.snapshot();
+        match self.advance(&mut adjusted) {
+            Ok(()) => {
+                *fitness = adjusted;
+                Ok(())
+            }
+            Err(e) => {
+                self.restore(saved);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn check_fitness(&self, fitness: &[f64]) -> Result<Vec<f64>, NeatError> {
+        //Validate fitness values for this population without touching anything,
+        //returns them with the fitness policy applied
+        self.config.validate()?;
+        if fitness.len() != self.population.len() {
+            return Err(NeatError::FitnessLength {
+                expected: self.population.len(),
+                found: fitness.len(),
+            });
+        }
+        let mut adjusted: Vec<f64> = fitness.to_vec();
+        self.config.fitness_policy.apply(&mut adjusted)?;
+        Ok(adjusted)
+    }
+
+    fn advance(&mut self, fitness: &mut [f64]) -> Result<(), NeatError> {
         if self.gen == 0 {
-            self.previous_gen = self.speciate(&self.population);
+            self.assign_species();
+            self.adjust_threshold();
         }
         println!(" number of species {}", self.previous_gen.len());
+        self.record_stats(fitness);
+        let allowed: Vec<bool> = self.remove_stagnant(fitness);
+        let elites: Vec<Genome> = self.global_elites(fitness);
+        let offspring_total: i32 = (self.population.len() - elites.len()) as i32;
+        self.parsimony(fitness);
         let mut assigned: Vec<i32> = vec![0; fitness.len()];
         let mut mapping: Vec<i32> = vec![0; self.population.len()];
         let mut idx: i32 = 0;
@@ -371,56 +884,240 @@ impl Population {
         for i in 0..fitness.len() {
             fitness[i] = fitness[i] / (assigned[i] as f64);
         }
-        let mut sum_fitness: f64 = 0.0;
         let mut species_fitness: Vec<f64> = vec![0.0; self.previous_gen.len()];
         for i in 0..fitness.len() {
-            sum_fitness += fitness[i];
+            if !allowed[mapping[i] as usize] {
+                continue;
+            }
             species_fitness[mapping[i] as usize] += fitness[i];
         }
+        let number_offspring: Vec<i32> =
+            self.allocate_offspring(&species_fitness, &allowed, offspring_total);
+        let mut new_gen: Vec<Genome> = elites;
+        let mut idx: usize = 0;
+        //for s in self.previous_gen {
+        for i in 0..self.previous_gen.len() {
+            if self.previous_gen[i].organisms.len() == 0 || number_offspring[idx] == 0 {
+                idx += 1;
+                continue;
+            }
+            let adding = self.create_species(i as i32, fitness, number_offspring[idx])?;
+            new_gen.extend(adding);
+            idx += 1;
+        }
+
+        if new_gen.len() != self.population.len() {
+            return Err(NeatError::InvalidConfig(format!(
+                "produced {} genomes for a population of {}",
+                new_gen.len(),
+                self.population.len()
+            )));
+        }
+        self.population = new_gen;
+        self.gen += 1;
+        self.assign_species();
+        self.adjust_threshold();
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            previous_gen: self.previous_gen.iter().map(|s| s.duplicate()).collect(),
+            next_species_id: self.next_species_id,
+            best_fitness: self.best_fitness,
+            last_improved: self.last_improved,
+            threshold: self.threshold,
+            stats: self.stats.len(),
+            inno_count: self.inno_count,
+            unique_nodes: self.unique_nodes,
+            inno_split: self.inno_split.clone(),
+            inno_edges: self.inno_edges.clone(),
+        }
+    }
+
+    fn restore(&mut self, saved: Snapshot) {
+        self.previous_gen = saved.previous_gen;
+        self.next_species_id = saved.next_species_id;
+        self.best_fitness = saved.best_fitness;
+        self.last_improved = saved.last_improved;
+        self.threshold = saved.threshold;
+        self.stats.truncate(saved.stats);
+        self.inno_count = saved.inno_count;
+        self.unique_nodes = saved.unique_nodes;
+        self.inno_split = saved.inno_split;
+        self.inno_edges = saved.inno_edges;
+    }
+
+    pub fn allocate_offspring(
+        &self,
+        species_fitness: &[f64],
+        allowed: &[bool],
+        offspring_total: i32,
+    ) -> Vec<i32> {
+        //Split the offspring between species proportional to their shared fitness
+        let sum_fitness: f64 = species_fitness.iter().sum();
         let mut number_offspring: Vec<i32> = vec![];
         let mut total: i32 = 0;
         let mut ss: Vec<(f64, i32)> = vec![];
         for i in 0..self.previous_gen.len() {
             ss.push((species_fitness[i], i as i32));
-            let val: i32 =
-                (species_fitness[i] * (self.population.len() as f64) / sum_fitness).floor() as i32;
+            let mut val: i32 =
+                (species_fitness[i] * (offspring_total as f64) / sum_fitness).floor() as i32;
+            if allowed[i] && !self.previous_gen[i].organisms.is_empty() {
+                val = i32::max(val, self.config.min_offspring);
+            }
             total += val;
             number_offspring.push(val);
         }
-        ss.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        ss.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+        while total > offspring_total {
+            //guaranteed offspring overshot the population so shrink the largest allocation
+            let largest = (0..number_offspring.len())
+                .max_by_key(|i| number_offspring[*i])
+                .unwrap();
+            number_offspring[largest] -= 1;
+            total -= 1;
+        }
         let mut idx: usize = 0;
-        while total < self.population.len() as i32 {
-            let numb = self.previous_gen[ss[idx].1 as usize].organisms.len() == 0;
-            if numb || idx == self.previous_gen.len() {
+        while total < offspring_total {
+            if idx == ss.len() {
                 idx = 0;
                 continue;
             }
+            let s = ss[idx].1 as usize;
+            if self.previous_gen[s].organisms.is_empty() || !allowed[s] {
+                idx += 1;
+                continue;
+            }
             number_offspring[ss[idx].1 as usize] += 1;
             idx += 1;
             total += 1;
         }
-        let mut new_gen: Vec<Genome> = vec![];
-        let mut idx: usize = 0;
-        //for s in self.previous_gen {
-        for i in 0..self.previous_gen.len() {
-            if self.previous_gen[i].organisms.len() == 0 || number_offspring[idx] == 0 {
-                idx += 1;
+        number_offspring
+    }
+
+    pub fn parsimony(&self, fitness: &mut [f64]) {
+        //Penalize every genome for its active connections and hidden nodes,
+        //penalized fitness never drops below zero
+        if self.config.connection_cost == 0.0 && self.config.node_cost == 0.0 {
+            return;
+        }
+        let mut scale: f64 = 1.0;
+        if self.config.adaptive_parsimony {
+            let total: f64 = self.population.iter().map(|g| g.complexity()).sum();
+            let mean = total / (usize::max(self.population.len(), 1) as f64);
+            //a genome without outputs has no minimal connections to compare against
+            let minimal = i32::max((self.inputs + 1) * self.outputs, 1) as f64;
+            scale = mean / minimal;
+        }
+        for (f, g) in fitness.iter_mut().zip(&self.population) {
+            let cost = self.config.connection_cost * (g.num_connections as f64)
+                + self.config.node_cost * (g.hidden_nodes as f64);
+            *f = f64::max(*f - scale * cost, 0.0);
+        }
+    }
+
+    pub fn global_elites(&self, fitness: &[f64]) -> Vec<Genome> {
+        //copies of the best genomes in the whole population regardless of species
+        let mut ranked: Vec<(f64, usize)> = vec![];
+        for (i, f) in fitness.iter().enumerate() {
+            ranked.push((*f, i));
+        }
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+        let n = i32::max(self.config.global_elitism, 0) as usize;
+        ranked
+            .iter()
+            .take(n)
+            .map(|(_, i)| self.population[*i].clone())
+            .collect()
+    }
+
+    pub fn adjust_threshold(&mut self) {
+        //Nudge the compatibility threshold so the number of species drifts toward the target
+        let target: i32 = match self.config.target_species {
+            Some(t) => t,
+            None => return,
+        };
+        let species = self.num_species();
+        if species < target {
+            self.threshold -= self.config.threshold_step;
+        } else if species > target {
+            self.threshold += self.config.threshold_step;
+        }
+        self.threshold = self
+            .threshold
+            .clamp(self.config.threshold_min, self.config.threshold_max);
+    }
+
+    pub fn num_species(&self) -> i32 {
+        self.previous_gen
+            .iter()
+            .filter(|s| !s.organisms.is_empty())
+            .count() as i32
+    }
+
+    pub fn record_stats(&mut self, fitness: &[f64]) {
+        let mut best: f64 = f64::NEG_INFINITY;
+        let mut sum: f64 = 0.0;
+        for f in fitness {
+            best = f64::max(best, *f);
+            sum += f;
+        }
+        self.stats.push(GenerationStats {
+            gen: self.gen,
+            species: self.num_species(),
+            threshold: self.threshold,
+            best_fitness: best,
+            mean_fitness: sum / (fitness.len() as f64),
+        });
+    }
+
+    pub fn remove_stagnant(&mut self, fitness: &[f64]) -> Vec<bool> {
+        //Update the improvement history of every species with the raw fitness values
+        //and return which species are still allowed to reproduce
+        let mut species_best: Vec<(f64, usize)> = vec![];
+        for (i, s) in self.previous_gen.iter_mut().enumerate() {
+            if s.organisms.is_empty() {
                 continue;
             }
-            let mut fit: Vec<f64> = vec![];
-            for a in &self.previous_gen[i].organisms {
-                //curr.push(&self.population[*a as usize]);
-                fit.push(fitness[*a as usize]);
+            let mut best: f64 = f64::NEG_INFINITY;
+            for a in &s.organisms {
+                best = f64::max(best, fitness[*a as usize]);
             }
-            let adding = self.create_species(i as i32, &fit, number_offspring[idx]);
-            new_gen.extend(adding);
-            idx += 1;
+            if best > s.best_fitness {
+                s.best_fitness = best;
+                s.last_improved = self.gen;
+            }
+            species_best.push((best, i));
         }
-
-        assert_eq!(new_gen.len(), self.population.len());
-        self.previous_gen = self.speciate(&new_gen);
-        self.population = new_gen;
-        self.gen += 1;
+        let mut allowed: Vec<bool> = vec![false; self.previous_gen.len()];
+        if species_best.is_empty() {
+            return allowed;
+        }
+        species_best.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+        if species_best[0].0 > self.best_fitness {
+            self.best_fitness = species_best[0].0;
+            self.last_improved = self.gen;
+        }
+        if self.gen - self.last_improved >= self.config.population_stagnation {
+            //the whole population stopped improving so only the top species reproduce
+            //and they get a fresh window to improve in
+            let survivors = i32::max(self.config.stagnant_survivors, 1) as usize;
+            for (_, i) in species_best.iter().take(survivors) {
+                allowed[*i] = true;
+                self.previous_gen[*i].last_improved = self.gen;
+            }
+            self.last_improved = self.gen;
+            return allowed;
+        }
+        for (rank, (_, i)) in species_best.iter().enumerate() {
+            let protected: bool = (rank as i32) < self.config.species_elitism;
+            let stagnant: i32 = self.gen - self.previous_gen[*i].last_improved;
+            allowed[*i] = protected || stagnant < self.config.stagnation_limit;
+        }
+        //never remove every species from the population
+        allowed[species_best[0].1] = true;
+        allowed
     }
 
     pub fn population_info(&self) {
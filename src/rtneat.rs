@@ -0,0 +1,134 @@
+use crate::constants::NO_CROSSING;
+use crate::error::NeatError;
+use crate::genome::Genome;
+use crate::helper::{chance, rand_f64, rand_i32};
+use crate::population::{Population, Species};
+
+pub struct RtNeat {
+    pub population: Population,
+    //latest fitness reported for every genome
+    pub fitness: Vec<f64>,
+    //ticks every genome has been alive
+    pub age: Vec<i32>,
+    pub tick: i32,
+    //one genome is replaced every interval ticks
+    pub interval: i32,
+    //genomes younger than this are never replaced
+    pub min_age: i32,
+}
+
+impl RtNeat {
+    pub fn new(mut population: Population, interval: i32, min_age: i32) -> Self {
+        population.assign_species();
+        let size = population.population.len();
+        Self {
+            population: population,
+            fitness: vec![0.0; size],
+            age: vec![0; size],
+            tick: 0,
+            interval: interval,
+            min_age: min_age,
+        }
+    }
+
+    pub fn step(&mut self) -> Result<Option<usize>, NeatError> {
+        //Advance one tick and every interval ticks replace the worst genome,
+        //returns the index of the replaced genome
+        self.tick += 1;
+        for a in &mut self.age {
+            *a += 1;
+        }
+        if self.interval <= 0 || self.tick % self.interval != 0 {
+            return Ok(None);
+        }
+        self.replace_worst()
+    }
+
+    pub fn worst(&self, fitness: &[f64]) -> Option<usize> {
+        //genome old enough to be judged with the lowest fitness shared by its species
+        let mut worst: Option<(f64, usize)> = None;
+        for s in &self.population.previous_gen {
+            for a in &s.organisms {
+                let i = *a as usize;
+                if self.age[i] < self.min_age {
+                    continue;
+                }
+                let adjusted = fitness[i] / (s.organisms.len() as f64);
+                if worst.map_or(true, |w| adjusted < w.0) {
+                    worst = Some((adjusted, i));
+                }
+            }
+        }
+        worst.map(|w| w.1)
+    }
+
+    pub fn parent_species(&self, fitness: &[f64]) -> usize {
+        //species picked with probability proportional to its average fitness
+        let species = &self.population.previous_gen;
+        let mut averages: Vec<f64> = vec![];
+        for s in species {
+            let total: f64 = s.organisms.iter().map(|a| fitness[*a as usize]).sum();
+            averages.push(total / (s.organisms.len() as f64));
+        }
+        let total: f64 = averages.iter().sum();
+        if total <= 0.0 {
+            return (rand_i32(1, species.len() as i32) - 1) as usize;
+        }
+        let mut spin: f64 = rand_f64(0.0, total);
+        for (i, a) in averages.iter().enumerate() {
+            spin -= a;
+            if spin <= 0.0 {
+                return i;
+            }
+        }
+        species.len() - 1
+    }
+
+    pub fn replace_worst(&mut self) -> Result<Option<usize>, NeatError> {
+        //Remove the worst genome and put an offspring of a fit species in its place
+        let mut fitness: Vec<f64> = self.fitness.clone();
+        self.population.config.fitness_policy.apply(&mut fitness)?;
+        let worst = match self.worst(&fitness) {
+            Some(w) => w,
+            None => return Ok(None),
+        };
+        //the genome being replaced can't be a parent of its replacement
+        let species: Vec<Species> = self
+            .population
+            .previous_gen
+            .iter()
+            .map(|s| s.duplicate())
+            .collect();
+        self.population.remove_from_species(worst as i32);
+        if self.population.previous_gen.is_empty() {
+            //a lone genome has nobody else to descend from
+            self.population.previous_gen = species;
+            return Ok(None);
+        }
+        let child = match self.offspring(&fitness) {
+            Ok(child) => child,
+            Err(e) => {
+                self.population.previous_gen = species;
+                return Err(e);
+            }
+        };
+        self.population.population[worst] = child;
+        self.fitness[worst] = 0.0;
+        self.age[worst] = 0;
+        self.population.place_genome(worst as i32);
+        Ok(Some(worst))
+    }
+
+    fn offspring(&mut self, fitness: &[f64]) -> Result<Genome, NeatError> {
+        let s = self.parent_species(fitness);
+        let best_ones = self.population.ranked_members(s, fitness);
+        let mut child = if best_ones.len() == 1 || chance(NO_CROSSING) {
+            let u = best_ones[self.population.config.selection.select(&best_ones)].1;
+            self.population.population[u as usize].clone()
+        } else {
+            self.population.crossover(s, &best_ones, fitness)?
+        };
+        self.population.try_mutate(&mut child)?;
+        Ok(child)
+    }
+}
@@ -0,0 +1,73 @@
+use crate::helper::{rand_f64, rand_i32};
+
+pub trait SelectionStrategy {
+    //ranked holds (fitness, organism) pairs sorted from most to least fit
+    //returns the position in ranked of the chosen parent
+    fn select(&self, ranked: &[(f64, i32)]) -> usize;
+}
+
+pub struct Truncation {
+    //parents are picked uniformly from this top fraction of the species
+    pub fraction: f64,
+}
+
+pub struct Tournament {
+    pub size: i32,
+}
+
+pub struct Roulette;
+
+pub struct Rank;
+
+impl SelectionStrategy for Truncation {
+    fn select(&self, ranked: &[(f64, i32)]) -> usize {
+        let top_members: i32 = ((ranked.len() as f64) * self.fraction).ceil() as i32;
+        let top_members: i32 = top_members.clamp(1, ranked.len() as i32);
+        (rand_i32(1, top_members) - 1) as usize
+    }
+}
+
+impl SelectionStrategy for Tournament {
+    fn select(&self, ranked: &[(f64, i32)]) -> usize {
+        //ranked is sorted so the best contestant is the one with the lowest position
+        let mut best: i32 = ranked.len() as i32 - 1;
+        for _ in 0..i32::max(self.size, 1) {
+            best = i32::min(best, rand_i32(0, ranked.len() as i32 - 1));
+        }
+        best as usize
+    }
+}
+
+impl SelectionStrategy for Roulette {
+    fn select(&self, ranked: &[(f64, i32)]) -> usize {
+        //shift by the least fit member so every slice of the wheel is non-negative
+        let lowest: f64 = ranked[ranked.len() - 1].0;
+        let total: f64 = ranked.iter().map(|r| r.0 - lowest).sum();
+        if total <= 0.0 {
+            return (rand_i32(1, ranked.len() as i32) - 1) as usize;
+        }
+        let mut spin: f64 = rand_f64(0.0, total);
+        for (i, r) in ranked.iter().enumerate() {
+            spin -= r.0 - lowest;
+            if spin <= 0.0 {
+                return i;
+            }
+        }
+        ranked.len() - 1
+    }
+}
+
+impl SelectionStrategy for Rank {
+    fn select(&self, ranked: &[(f64, i32)]) -> usize {
+        //linear ranking, the best of n members gets weight n and the worst weight 1
+        let n: i32 = ranked.len() as i32;
+        let mut spin: i32 = rand_i32(1, n * (n + 1) / 2);
+        for i in 0..n {
+            spin -= n - i;
+            if spin <= 0 {
+                return i as usize;
+            }
+        }
+        (n - 1) as usize
+    }
+}
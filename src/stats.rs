@@ -0,0 +1,7 @@
+pub struct GenerationStats {
+    pub gen: i32,
+    pub species: i32,
+    pub threshold: f64,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+}
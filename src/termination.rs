@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::genome::Genome;
+
+pub struct Progress {
+    //generations evaluated since the run started
+    pub generations: i32,
+    pub evaluations: i64,
+    pub elapsed: Duration,
+    //best fitness of the run and the genome that reached it
+    pub best_fitness: f64,
+    pub champion: Option<Genome>,
+    //generations evaluated when the best fitness last improved
+    pub last_improved: i32,
+    //fitness of the generation just evaluated and its fittest genome
+    pub fitness: Vec<f64>,
+    pub generation_best: Option<Genome>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    FitnessThreshold,
+    MaxGenerations,
+    MaxEvaluations,
+    WallClock,
+    NoImprovement,
+    //name of the custom predicate that stopped the run
+    Custom(String),
+}
+
+pub enum Criterion {
+    //best fitness reached at least this value
+    FitnessThreshold(f64),
+    MaxGenerations(i32),
+    MaxEvaluations(i64),
+    WallClock(Duration),
+    //generations without the best fitness improving
+    NoImprovement(i32),
+    Custom(String, Box<dyn Fn(&Progress) -> bool>),
+}
+
+impl Criterion {
+    pub fn check(&self, progress: &Progress) -> Option<StopReason> {
+        let stop = match self {
+            Criterion::FitnessThreshold(f) => progress.best_fitness >= *f,
+            Criterion::MaxGenerations(n) => progress.generations >= *n,
+            Criterion::MaxEvaluations(n) => progress.evaluations >= *n,
+            Criterion::WallClock(limit) => progress.elapsed >= *limit,
+            Criterion::NoImprovement(n) => progress.generations - progress.last_improved >= *n,
+            Criterion::Custom(_, predicate) => predicate(progress),
+        };
+        if !stop {
+            return None;
+        }
+        Some(match self {
+            Criterion::FitnessThreshold(_) => StopReason::FitnessThreshold,
+            Criterion::MaxGenerations(_) => StopReason::MaxGenerations,
+            Criterion::MaxEvaluations(_) => StopReason::MaxEvaluations,
+            Criterion::WallClock(_) => StopReason::WallClock,
+            Criterion::NoImprovement(_) => StopReason::NoImprovement,
+            Criterion::Custom(name, _) => StopReason::Custom(name.clone()),
+        })
+    }
+}
+
+pub struct Termination {
+    //the run stops as soon as any criterion is met, checked in order
+    pub criteria: Vec<Criterion>,
+}
+
+impl Default for Termination {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Termination {
+    pub fn new() -> Self {
+        Self { criteria: vec![] }
+    }
+
+    pub fn fitness_threshold(mut self, fitness: f64) -> Self {
+        self.criteria.push(Criterion::FitnessThreshold(fitness));
+        self
+    }
+
+    pub fn max_generations(mut self, generations: i32) -> Self {
+        self.criteria.push(Criterion::MaxGenerations(generations));
+        self
+    }
+
+    pub fn max_evaluations(mut self, evaluations: i64) -> Self {
+        self.criteria.push(Criterion::MaxEvaluations(evaluations));
+        self
+    }
+
+    pub fn wall_clock(mut self, limit: Duration) -> Self {
+        self.criteria.push(Criterion::WallClock(limit));
+        self
+    }
+
+    pub fn no_improvement(mut self, generations: i32) -> Self {
+        self.criteria.push(Criterion::NoImprovement(generations));
+        self
+    }
+
+    pub fn custom(mut self, name: &str, predicate: impl Fn(&Progress) -> bool + 'static) -> Self {
+        self.criteria
+            .push(Criterion::Custom(name.to_string(), Box::new(predicate)));
+        self
+    }
+
+    pub fn check(&self, progress: &Progress) -> Option<StopReason> {
+        self.criteria.iter().find_map(|c| c.check(progress))
+    }
+}
+
+pub struct Outcome {
+    pub champion: Genome,
+    pub best_fitness: f64,
+    pub reason: StopReason,
+    pub generations: i32,
+    pub evaluations: i64,
+}
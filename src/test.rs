@@ -1,8 +1,41 @@
 #[cfg(test)]
 mod tests {
     use std::assert_eq;
+    use std::cell::RefCell;
+    use std::collections::BTreeSet;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpStream;
+    use std::rc::Rc;
+    use std::thread;
+    use std::time::Duration;
 
-    use crate::{activation::ignore, genome::Genome, population::Population};
+    use crate::{
+        activation::{ignore, sigmoid, tanh},
+        benchmarks::{
+            by_name, iris, multiplexer, parity, try_polynomial, try_sine, two_spirals, xor,
+        },
+        cli::{evaluate_parallel, Experiment, Options},
+        coevolution::Coevolution,
+        config::Config,
+        cooperative::{compose, Cooperative},
+        dataset::{Dataset, Loss, Supervised},
+        distributed::{run_worker, Coordinator},
+        environments::{double_pole_non_markov, single_pole, CartPole, DoublePole},
+        episode::{Environment, EpisodeRunner, Recurrent},
+        error::NeatError,
+        eshyperneat::{EsHyperNeat, QuadPoint},
+        fitness::FitnessPolicy,
+        genome::{Genome, GenomeInfo},
+        harness::Harness,
+        hyperneat::{HyperNeat, Substrate},
+        island::{remap, Islands, Topology},
+        novelty::{distance, normalize, novelty},
+        pareto::{crowding_distance, dominates, non_dominated_sort, pareto_fitness},
+        population::{Population, Species},
+        rtneat::RtNeat,
+        selection::{Rank, Roulette, SelectionStrategy, Tournament, Truncation},
+        termination::{StopReason, Termination},
+    };
     #[test]
     fn fully_connected() {
         let mut g = Genome::new(4, 4, ignore);
@@ -138,4 +171,1471 @@ mod tests {
         p1.next_generation(&mut outs);
         assert!(p1.population[0].num_connections <= 2 || p1.population[0].num_nodes <= 3);
     }
+
+    fn make_species(gen: i32, organisms: Vec<i32>) -> Species {
+        let mut s = Species::new(0, gen, Genome::new(1, 1, ignore));
+        s.organisms = organisms;
+        s
+    }
+
+    #[test]
+    fn stagnant_species_removed() {
+        let mut p = Population::new(4, 1, 1, ignore, true);
+        p.config.species_elitism = 1;
+        p.gen = 20;
+        p.best_fitness = 0.0;
+        p.previous_gen = vec![make_species(0, vec![0, 1]), make_species(0, vec![2, 3])];
+        p.previous_gen[1].best_fitness = 10.0;
+        let allowed = p.remove_stagnant(&vec![5.0, 4.0, 1.0, 1.0]);
+        assert_eq!(allowed, vec![true, false]);
+        assert_eq!(p.previous_gen[0].last_improved, 20);
+        assert_eq!(p.previous_gen[0].best_fitness, 5.0);
+        assert_eq!(p.last_improved, 20);
+    }
+
+    #[test]
+    fn population_stagnation_keeps_top_species() {
+        let mut p = Population::new(6, 1, 1, ignore, true);
+        p.gen = 25;
+        p.best_fitness = 100.0;
+        p.previous_gen = vec![
+            make_species(25, vec![0, 1]),
+            make_species(25, vec![2, 3]),
+            make_species(25, vec![4, 5]),
+        ];
+        let allowed = p.remove_stagnant(&vec![1.0, 1.0, 3.0, 3.0, 2.0, 2.0]);
+        assert_eq!(allowed, vec![false, true, true]);
+        assert_eq!(p.last_improved, 25);
+    }
+
+    #[test]
+    fn species_age_tracked() {
+        fn metric(_inputs: &Vec<f64>, _outputs: &Vec<f64>) -> f64 {
+            return 1.0;
+        }
+        let mut p = Population::new(20, 1, 1, ignore, true);
+        for _ in 0..3 {
+            let mut outs = p.evaluate_all(&vec![1.0], metric);
+            p.next_generation(&mut outs);
+            assert_eq!(p.population.len(), 20);
+        }
+        assert_eq!(p.gen, 3);
+        assert_eq!(p.last_improved, 0);
+        for s in &p.previous_gen {
+            assert_eq!(s.age, p.gen - s.created);
+        }
+
+        //a threshold no genome can exceed keeps a single species alive from the start
+        let mut p = Population::new(20, 1, 1, ignore, true);
+        p.threshold = 1000.0;
+        for gen in 1..=3 {
+            let mut outs = p.evaluate_all(&vec![1.0], metric);
+            p.next_generation(&mut outs);
+            assert_eq!(p.previous_gen.len(), 1);
+            assert_eq!(p.previous_gen[0].created, 0);
+            assert_eq!(p.previous_gen[0].age, gen);
+        }
+    }
+
+    #[test]
+    fn threshold_targets_species_count() {
+        let mut p = Population::new(4, 1, 1, ignore, true);
+        p.config.target_species = Some(3);
+        p.config.threshold_step = 0.5;
+        p.config.threshold_min = 2.0;
+        p.previous_gen = vec![make_species(0, vec![0, 1, 2, 3])];
+        p.adjust_threshold();
+        assert_eq!(p.threshold, 2.5);
+        p.adjust_threshold();
+        p.adjust_threshold();
+        assert_eq!(p.threshold, 2.0);
+        p.previous_gen = (0..4).map(|i| make_species(0, vec![i])).collect();
+        p.adjust_threshold();
+        assert_eq!(p.threshold, 2.5);
+    }
+
+    #[test]
+    fn fixed_threshold_records_stats() {
+        fn metric(_inputs: &Vec<f64>, _outputs: &Vec<f64>) -> f64 {
+            return 2.0;
+        }
+        let mut p = Population::new(10, 1, 1, ignore, true);
+        for _ in 0..2 {
+            let mut outs = p.evaluate_all(&vec![1.0], metric);
+            p.next_generation(&mut outs);
+        }
+        assert_eq!(p.threshold, 3.0);
+        assert_eq!(p.stats.len(), 2);
+        assert_eq!(p.stats[1].gen, 1);
+        assert_eq!(p.stats[1].best_fitness, 2.0);
+        assert_eq!(p.stats[1].mean_fitness, 2.0);
+    }
+
+    #[test]
+    fn species_keep_identity() {
+        fn metric(_inputs: &Vec<f64>, _outputs: &Vec<f64>) -> f64 {
+            return 1.0;
+        }
+        let mut p = Population::new(30, 1, 1, ignore, true);
+        for _ in 0..3 {
+            let mut outs = p.evaluate_all(&vec![1.0], metric);
+            p.next_generation(&mut outs);
+            let ids: BTreeSet<i32> = p.previous_gen.iter().map(|s| s.id).collect();
+            assert_eq!(ids.len(), p.previous_gen.len());
+            for s in &p.previous_gen {
+                assert!(s.id < p.next_species_id);
+                assert!(s.created <= p.gen);
+                assert!(s.organisms.contains(&s.leader));
+                assert_eq!(p.species_of(s.leader), Some(s.id));
+                assert!(p.classify(&s.representative).is_some());
+                assert_eq!(p.get_species(s.id).unwrap().created, s.created);
+            }
+        }
+        let assigned: usize = p.previous_gen.iter().map(|s| s.organisms.len()).sum();
+        assert_eq!(assigned, 30);
+    }
+
+    #[test]
+    fn selection_strategies() {
+        let ranked: Vec<(f64, i32)> = (0..8).map(|i| ((8 - i) as f64, i)).collect();
+        let truncation = Truncation { fraction: 0.25 };
+        let tournament = Tournament { size: 1000 };
+        for _ in 0..100 {
+            assert!(truncation.select(&ranked) < 2);
+            assert_eq!(tournament.select(&ranked), 0);
+            assert!(Rank.select(&ranked) < 8);
+            assert!(Roulette.select(&ranked) < 7);
+        }
+        let lopsided: Vec<(f64, i32)> = vec![(5.0, 0), (0.0, 1), (0.0, 2)];
+        assert_eq!(Roulette.select(&lopsided), 0);
+        let flat: Vec<(f64, i32)> = vec![(1.0, 0), (1.0, 1)];
+        assert!(Roulette.select(&flat) < 2);
+    }
+
+    #[test]
+    fn interspecies_mating() {
+        fn metric(_inputs: &Vec<f64>, _outputs: &Vec<f64>) -> f64 {
+            return 1.0;
+        }
+        let mut p = Population::new(30, 1, 1, ignore, true);
+        p.config.interspecies_rate = 1.0;
+        p.config.selection = Box::new(Tournament { size: 3 });
+        for _ in 0..3 {
+            let mut outs = p.evaluate_all(&vec![1.0], metric);
+            p.next_generation(&mut outs);
+            assert_eq!(p.population.len(), 30);
+        }
+        let fitness: Vec<f64> = vec![1.0; 30];
+        if p.previous_gen.len() > 1 {
+            let other = p.interspecies_parent(0, &fitness).unwrap();
+            assert!(!p.previous_gen[0].organisms.contains(&other.1));
+        }
+    }
+
+    fn weights(g: &Genome) -> Vec<f64> {
+        g.flatten().iter().map(|e| e.weight).collect()
+    }
+
+    #[test]
+    fn species_elitism_keeps_champions() {
+        let mut p = Population::new(8, 2, 1, ignore, true);
+        p.config.elitism = 3;
+        p.config.elitism_min_size = 2;
+        p.previous_gen = vec![make_species(0, (0..8).collect())];
+        let fitness: Vec<f64> = (0..8).map(|i| i as f64).collect();
+        let children = p.create_species(0, &fitness, 5).unwrap();
+        assert_eq!(children.len(), 5);
+        for k in 0..3 {
+            assert_eq!(weights(&children[k]), weights(&p.population[7 - k]));
+        }
+        p.config.elitism_min_size = 9;
+        let children = p.create_species(0, &fitness, 1).unwrap();
+        assert_eq!(children.len(), 1);
+    }
+
+    #[test]
+    fn global_elitism_keeps_best_genomes() {
+        let mut p = Population::new(10, 2, 1, ignore, true);
+        p.config.global_elitism = 2;
+        let best = weights(&p.population[3]);
+        let second = weights(&p.population[6]);
+        let mut fitness: Vec<f64> = vec![1.0; 10];
+        fitness[3] = 10.0;
+        fitness[6] = 5.0;
+        p.next_generation(&mut fitness);
+        assert_eq!(p.population.len(), 10);
+        assert_eq!(weights(&p.population[0]), best);
+        assert_eq!(weights(&p.population[1]), second);
+    }
+
+    #[test]
+    fn minimum_offspring_per_species() {
+        let mut p = Population::new(10, 1, 1, ignore, true);
+        p.previous_gen = vec![
+            make_species(0, (0..5).collect()),
+            make_species(0, (5..10).collect()),
+        ];
+        let allowed = vec![true, true];
+        assert_eq!(p.allocate_offspring(&[1.0, 0.0], &allowed, 10), vec![10, 0]);
+        p.config.min_offspring = 3;
+        assert_eq!(p.allocate_offspring(&[1.0, 0.0], &allowed, 10), vec![7, 3]);
+        assert_eq!(
+            p.allocate_offspring(&[1.0, 0.0], &[true, false], 10),
+            vec![10, 0]
+        );
+    }
+
+    #[test]
+    fn invalid_edges_reported() {
+        let mut g = Genome::new(1, 1, ignore);
+        assert_eq!(
+            g.try_add_edge(2, 2, 0, 1.0, true),
+            Err(NeatError::SelfLoop(2))
+        );
+        assert_eq!(
+            g.try_add_edge(0, 7, 0, 1.0, true),
+            Err(NeatError::NodeOutOfRange { node: 7, nodes: 3 })
+        );
+        assert_eq!(g.num_connections, 0);
+        assert_eq!(
+            g.try_evaluate(&vec![1.0, 2.0]),
+            Err(NeatError::InputLength {
+                expected: 1,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn corrupted_genes_reported() {
+        let genes = vec![GenomeInfo {
+            from: -4,
+            to: 2,
+            innovation_number: 0,
+            weight: 1.0,
+            active: true,
+        }];
+        let g = Genome::try_un_flatten(&genes, 1, 1, ignore);
+        assert_eq!(g.err(), Some(NeatError::UnknownNode(-4)));
+        //an unconnected input keeps every other node in place
+        let genes = vec![GenomeInfo {
+            from: 1,
+            to: 3,
+            innovation_number: 0,
+            weight: 2.0,
+            active: true,
+        }];
+        let g = Genome::try_un_flatten(&genes, 2, 1, ignore).unwrap();
+        assert_eq!(g.evaluate(&vec![5.0, 7.0]), vec![14.0]);
+    }
+
+    #[test]
+    fn bad_fitness_reported() {
+        let mut p = Population::new(4, 1, 1, ignore, true);
+        p.config.fitness_policy = FitnessPolicy::Reject;
+        assert_eq!(
+            p.try_next_generation(&mut vec![1.0; 3]),
+            Err(NeatError::FitnessLength {
+                expected: 4,
+                found: 3
+            })
+        );
+        let err = p.try_next_generation(&mut vec![1.0, f64::NAN, 1.0, 1.0]);
+        assert!(matches!(
+            err,
+            Err(NeatError::InvalidFitness { index: 1, .. })
+        ));
+        assert_eq!(p.gen, 0);
+        p.config.threshold_min = p.config.threshold_max + 1.0;
+        assert!(matches!(
+            p.try_next_generation(&mut vec![1.0; 4]),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        assert_eq!(p.gen, 0);
+        p.config.threshold_min = 0.3;
+        p.config.min_offspring = -2;
+        assert!(matches!(
+            p.try_next_generation(&mut vec![1.0; 4]),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        p.config.min_offspring = 0;
+        assert!(p.try_next_generation(&mut vec![1.0; 4]).is_ok());
+        assert_eq!(p.gen, 1);
+    }
+
+    #[test]
+    fn fitness_policies() {
+        let raw = vec![2.0, f64::NAN, -3.0, f64::INFINITY, f64::NEG_INFINITY];
+        let mut fitness = raw.clone();
+        let err = FitnessPolicy::Reject.apply(&mut fitness);
+        assert!(matches!(
+            err,
+            Err(NeatError::InvalidFitness { index: 1, .. })
+        ));
+        let err = FitnessPolicy::Reject.apply(&mut [1.0, -1.0]);
+        assert!(matches!(
+            err,
+            Err(NeatError::InvalidFitness { index: 1, .. })
+        ));
+
+        let err = FitnessPolicy::Clamp(-1.0).apply(&mut [1.0]);
+        assert!(matches!(err, Err(NeatError::InvalidConfig(_))));
+
+        let mut fitness = raw.clone();
+        FitnessPolicy::Clamp(0.5).apply(&mut fitness).unwrap();
+        assert_eq!(fitness, vec![2.0, 0.5, 0.5, 2.0, 0.5]);
+
+        let mut fitness = raw.clone();
+        FitnessPolicy::Shift.apply(&mut fitness).unwrap();
+        assert_eq!(fitness, vec![5.0, 0.0, 0.0, 5.0, 0.0]);
+
+        let mut fitness = vec![f64::NAN; 3];
+        FitnessPolicy::Shift.apply(&mut fitness).unwrap();
+        assert_eq!(fitness, vec![0.0; 3]);
+    }
+
+    #[test]
+    fn negative_fitness_generation() {
+        let mut p = Population::new(10, 1, 1, ignore, true);
+        p.config.fitness_policy = FitnessPolicy::Shift;
+        let mut fitness: Vec<f64> = (0..10).map(|i| -(i as f64)).collect();
+        fitness[4] = f64::NAN;
+        p.try_next_generation(&mut fitness).unwrap();
+        assert_eq!(p.population.len(), 10);
+    }
+
+    #[test]
+    fn validate_genomes() {
+        let mut g = Genome::new(3, 2, ignore);
+        g.connect_ends();
+        assert!(g.validate().is_valid());
+        let mut inno = 100;
+        for i in 0..5 {
+            let e = g.random_split();
+            g.split_edge(e.0, e.1, inno, 50 + i);
+            inno += 2;
+            let e = g.random_edge();
+            if g.edge_exist(e.0, e.1) {
+                g.enable_edge(e.0, e.1);
+            } else if e.0 != -1 {
+                g.add_edge(e.0, e.1, inno, 1.0, true);
+                inno += 1;
+            }
+        }
+        let (active, total) = (g.num_connections, g.total_connections());
+        assert!(active < total);
+        assert!(g.random_disable());
+        assert_eq!(g.num_connections, active - 1);
+        assert_eq!(g.total_connections(), total);
+        assert!(g.validate().is_valid(), "{}", g.validate());
+        assert!(g.clone().validate().is_valid());
+
+        g.num_connections += 1;
+        g.hidden_nodes += 1;
+        g.edges.insert((3, 4));
+        g.nodes[0].borrow_mut().active_edges += 1;
+        let report = g.validate();
+        assert_eq!(report.errors.len(), 4, "{}", report);
+    }
+
+    #[test]
+    fn validate_detects_cycles_and_innovations() {
+        let mut g = Genome::new(1, 1, ignore);
+        let h = g.add_node(5);
+        g.add_edge(0, h, 0, 1.0, true);
+        g.add_edge(h, 2, 0, 1.0, true);
+        g.add_edge(2, h, 1, 1.0, true);
+        let report = g.validate();
+        assert!(report
+            .errors
+            .contains(&String::from("innovation number 0 is used twice")));
+        assert!(report
+            .errors
+            .contains(&String::from("active edges form a cycle")));
+    }
+
+    #[test]
+    fn validate_every_generation() {
+        fn metric(_inputs: &Vec<f64>, outputs: &Vec<f64>) -> f64 {
+            outputs[0].abs()
+        }
+        let mut p = Population::new(30, 2, 1, ignore, true);
+        p.config.validate_genomes = true;
+        for _ in 0..10 {
+            let mut outs = p.evaluate_all(&vec![1.0, 0.5], metric);
+            p.try_next_generation(&mut outs).unwrap();
+        }
+        for g in &p.population {
+            assert!(g.validate().is_valid());
+        }
+    }
+
+    #[test]
+    fn substrate_layouts() {
+        let s = Substrate::layered(&[3, 2, 1]);
+        assert_eq!(
+            s.layers[0],
+            vec![vec![-1.0, -1.0], vec![0.0, -1.0], vec![1.0, -1.0]]
+        );
+        assert_eq!(s.layers[2], vec![vec![0.0, 1.0]]);
+        assert_eq!((s.inputs(), s.outputs(), s.dimension()), (3, 1, 2));
+        let s = Substrate::grid(&[(2, 2), (1, 1)]);
+        assert_eq!(s.layers[0].len(), 4);
+        assert_eq!(s.layers[0][3], vec![1.0, 1.0, -1.0]);
+        assert_eq!(s.dimension(), 3);
+
+        assert!(matches!(
+            Substrate::try_new(vec![vec![vec![0.0]]]),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        assert!(Substrate::try_new(vec![vec![vec![0.0]], vec![]]).is_err());
+        assert!(Substrate::try_new(vec![vec![vec![0.0]], vec![vec![0.0, 1.0]]]).is_err());
+        assert!(Substrate::try_new(vec![vec![vec![0.0]], vec![vec![1.0]]]).is_ok());
+    }
+
+    #[test]
+    fn hyperneat_builds_phenotype() {
+        let hn = HyperNeat::new(Substrate::layered(&[2, 3, 1]), ignore);
+        assert_eq!(hn.cppn_inputs(), 4);
+        let mut cppn = Genome::new(4, 1, tanh);
+        //constant CPPN output tanh(1) from the bias
+        cppn.add_edge(4, 5, 0, 1.0, true);
+        let net = hn.build(&cppn);
+        assert_eq!(net.num_nodes, 2 + 1 + 1 + 3);
+        assert_eq!(net.num_connections, 2 * 3 + 3);
+        assert!(net.validate().is_valid());
+        let w = (f64::tanh(1.0) - 0.2) / 0.8 * 3.0;
+        let out = net.evaluate(&vec![1.0, 1.0]);
+        assert!((out[0] - 6.0 * w * w).abs() < 1e-9);
+
+        //below the threshold nothing is expressed
+        let mut weak = Genome::new(4, 1, tanh);
+        weak.add_edge(4, 5, 0, 0.1, true);
+        assert_eq!(hn.build(&weak).num_connections, 0);
+    }
+
+    #[test]
+    fn hyperneat_link_expression() {
+        let mut hn = HyperNeat::new(Substrate::layered(&[2, 1]), ignore);
+        hn.leo = true;
+        assert_eq!(hn.cppn_outputs(), 2);
+        let mut cppn = Genome::new(4, 2, tanh);
+        cppn.add_edge(4, 5, 0, 0.1, true);
+        //express only connections whose source x coordinate is positive
+        cppn.add_edge(0, 6, 1, 5.0, true);
+        let net = hn.build(&cppn);
+        assert_eq!(net.num_connections, 1);
+        assert!(net.edge_exist(1, 3));
+        //a CPPN without the link expression output or with the wrong inputs is refused
+        assert!(matches!(
+            hn.try_build(&Genome::new(4, 1, tanh)),
+            Err(NeatError::InvalidGenome(_))
+        ));
+        assert!(matches!(
+            hn.try_build(&Genome::new(2, 2, tanh)),
+            Err(NeatError::InvalidGenome(_))
+        ));
+    }
+
+    #[test]
+    fn quadtree_variance() {
+        let mut p = QuadPoint::new(0.0, 0.0, 1.0, 1);
+        assert_eq!(p.variance(), 0.0);
+        for w in [0.0, 1.0, 0.0, 1.0] {
+            let mut c = QuadPoint::new(0.0, 0.0, 0.5, 2);
+            c.weight = w;
+            p.children.push(c);
+        }
+        assert_eq!(p.variance(), 0.25);
+    }
+
+    #[test]
+    fn es_hyperneat_discovers_hidden_nodes() {
+        let es = EsHyperNeat::new(vec![(0.0, -1.0), (0.0, -0.5)], vec![(0.0, 1.0)], tanh);
+        //a uniform CPPN has no band of variation so nothing is connected
+        let flat = Genome::new(4, 1, tanh);
+        assert_eq!(es.build(&flat).num_connections, 0);
+
+        //weights change sharply around x = 0 for both the source and the target
+        let mut cppn = Genome::new(4, 1, tanh);
+        cppn.add_edge(0, 5, 0, 5.0, true);
+        cppn.add_edge(2, 5, 1, 5.0, true);
+        let net = es.build(&cppn);
+        assert!(net.validate().is_valid(), "{}", net.validate());
+        assert!(net.hidden_nodes > 0);
+        assert!(net.num_connections > 0);
+        assert_eq!(net.evaluate(&vec![1.0, -1.0]).len(), 1);
+        assert!(matches!(
+            es.try_build(&Genome::new(2, 1, tanh)),
+            Err(NeatError::InvalidGenome(_))
+        ));
+        assert!(matches!(
+            es.try_build(&Genome::new(4, 0, tanh)),
+            Err(NeatError::InvalidGenome(_))
+        ));
+    }
+
+    #[test]
+    fn novelty_nearest_neighbours() {
+        assert_eq!(distance(&[0.0, 0.0], &[3.0, 4.0]), 5.0);
+        let behaviors = vec![vec![0.0], vec![1.0], vec![3.0], vec![10.0]];
+        assert_eq!(novelty(0, &behaviors, &[], 1), 1.0);
+        assert_eq!(novelty(0, &behaviors, &[], 2), 2.0);
+        assert_eq!(novelty(3, &behaviors, &[vec![9.0]], 2), 4.0);
+        assert_eq!(novelty(0, &[vec![2.0]], &[], 5), 0.0);
+        assert_eq!(normalize(&[1.0, 3.0, 2.0]), vec![0.0, 1.0, 0.5]);
+        assert_eq!(normalize(&[2.0, 2.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn novelty_archive_grows() {
+        let mut p = Population::new(4, 1, 1, ignore, true);
+        p.config.novelty_neighbours = 1;
+        p.config.archive_threshold = 2.0;
+        let behaviors = vec![vec![0.0], vec![1.0], vec![5.0], vec![20.0]];
+        let scores = p.novelty_scores(&behaviors);
+        assert_eq!(scores, vec![1.0, 1.0, 4.0, 15.0]);
+        assert_eq!(p.archive, vec![vec![5.0], vec![20.0]]);
+    }
+
+    #[test]
+    fn novelty_generation() {
+        fn behavior(_inputs: &Vec<f64>, outputs: &Vec<f64>) -> Vec<f64> {
+            outputs.clone()
+        }
+        let mut p = Population::new(20, 1, 1, ignore, true);
+        p.config.archive_threshold = 0.0;
+        for _ in 0..3 {
+            let behaviors = p.evaluate_behaviors(&vec![1.0], behavior);
+            p.try_next_generation_novelty(&behaviors, &[]).unwrap();
+        }
+        assert_eq!(p.gen, 3);
+        assert!(!p.archive.is_empty());
+        p.config.novelty_weight = 0.5;
+        let behaviors = p.evaluate_behaviors(&vec![1.0], behavior);
+        let err = p.try_next_generation_novelty(&behaviors, &[]);
+        assert!(matches!(err, Err(NeatError::FitnessLength { .. })));
+        p.try_next_generation_novelty(&behaviors, &[1.0; 20])
+            .unwrap();
+        //a failed generation leaves the archive as it was
+        let archived = p.archive.clone();
+        p.config.archive_threshold = -1.0;
+        p.config.threshold_min = p.config.threshold_max + 1.0;
+        let behaviors = p.evaluate_behaviors(&vec![1.0], behavior);
+        let err = p.try_next_generation_novelty(&behaviors, &[1.0; 20]);
+        assert!(matches!(err, Err(NeatError::InvalidConfig(_))));
+        assert_eq!(p.archive, archived);
+    }
+
+    #[test]
+    fn pareto_fronts() {
+        assert!(dominates(&[2.0, 2.0], &[1.0, 2.0]));
+        assert!(!dominates(&[2.0, 2.0], &[2.0, 2.0]));
+        assert!(!dominates(&[3.0, 0.0], &[0.0, 3.0]));
+        let objectives = vec![
+            vec![3.0, 0.0],
+            vec![0.0, 3.0],
+            vec![2.0, 2.0],
+            vec![1.0, 1.0],
+            vec![0.0, 0.0],
+            vec![1.0, 0.5],
+        ];
+        let fronts = non_dominated_sort(&objectives);
+        assert_eq!(fronts, vec![vec![0, 1, 2], vec![3], vec![5], vec![4]]);
+        let crowding = crowding_distance(&objectives, &fronts[0]);
+        assert!(crowding[0].is_infinite() && crowding[1].is_infinite());
+        assert_eq!(crowding[2], 2.0);
+        let fitness = pareto_fitness(&objectives);
+        assert!(fitness[0] > fitness[2] && fitness[2] > fitness[3]);
+        assert!(fitness[3] > fitness[5] && fitness[5] > fitness[4]);
+    }
+
+    #[test]
+    fn multi_objective_generation() {
+        fn objectives(_inputs: &Vec<f64>, outputs: &Vec<f64>) -> Vec<f64> {
+            vec![outputs[0], -outputs[0].abs()]
+        }
+        let mut p = Population::new(20, 1, 1, ignore, true);
+        p.config.complexity_objective = true;
+        for _ in 0..3 {
+            let o = p.evaluate_objectives(&vec![1.0], objectives);
+            p.try_next_generation_multi(&o).unwrap();
+        }
+        assert_eq!(p.population.len(), 20);
+        let err = p.try_next_generation_multi(&vec![vec![f64::NAN]; 20]);
+        assert!(matches!(
+            err,
+            Err(NeatError::InvalidFitness { index: 0, .. })
+        ));
+        let mut uneven = vec![vec![1.0, 2.0]; 20];
+        uneven[7].pop();
+        assert_eq!(
+            p.try_next_generation_multi(&uneven),
+            Err(NeatError::ObjectiveLength {
+                index: 7,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn parsimony_penalty() {
+        let mut p = Population::new(2, 1, 1, ignore, true);
+        let mut bigger = p.population[1].clone();
+        p.random_split(&mut bigger);
+        p.population[1] = bigger;
+        assert_eq!(p.population[1].num_connections, 3);
+        assert_eq!(p.population[1].hidden_nodes, 1);
+        assert_eq!(p.population[1].complexity(), 4.0);
+
+        let mut fitness = vec![10.0, 10.0];
+        p.parsimony(&mut fitness);
+        assert_eq!(fitness, vec![10.0, 10.0]);
+
+        p.config.connection_cost = 1.0;
+        p.config.node_cost = 2.0;
+        p.parsimony(&mut fitness);
+        assert_eq!(fitness, vec![8.0, 5.0]);
+
+        //mean complexity is 3 against 2 connections for a minimal genome
+        p.config.adaptive_parsimony = true;
+        let mut fitness = vec![10.0, 10.0];
+        p.parsimony(&mut fitness);
+        assert_eq!(fitness, vec![7.0, 2.5]);
+        let mut fitness = vec![1.0, 1.0];
+        p.parsimony(&mut fitness);
+        assert_eq!(fitness, vec![0.0, 0.0]);
+
+        let mut p = Population::new(2, 1, 0, ignore, true);
+        p.config.connection_cost = 1.0;
+        p.config.adaptive_parsimony = true;
+        let mut fitness = vec![1.0, 1.0];
+        p.parsimony(&mut fitness);
+        assert_eq!(fitness, vec![1.0, 1.0]);
+    }
+
+    #[test]
+    fn rtneat_replacement() {
+        let p = Population::new(10, 2, 1, ignore, true);
+        let mut rt = RtNeat::new(p, 3, 4);
+        for i in 0..10 {
+            rt.fitness[i] = (i + 1) as f64;
+        }
+        //nobody is old enough before tick 4
+        assert_eq!(rt.step().unwrap(), None);
+        assert_eq!(rt.step().unwrap(), None);
+        assert_eq!(rt.step().unwrap(), None);
+        for _ in 0..2 {
+            rt.step().unwrap();
+        }
+        let worst = rt.worst(&rt.fitness);
+        assert!(worst.is_some());
+        let replaced = rt.step().unwrap();
+        assert_eq!(replaced, worst);
+        let worst = worst.unwrap();
+        assert_eq!(rt.age[worst], 0);
+        assert_eq!(rt.fitness[worst], 0.0);
+        assert_eq!(rt.population.population.len(), 10);
+        assert!(rt.population.species_of(worst as i32).is_some());
+        let members: usize = rt
+            .population
+            .previous_gen
+            .iter()
+            .map(|s| s.organisms.len())
+            .sum();
+        assert_eq!(members, 10);
+        for _ in 0..60 {
+            rt.step().unwrap();
+        }
+        for g in &rt.population.population {
+            assert!(g.validate().is_valid());
+        }
+    }
+
+    #[test]
+    fn rtneat_worst_is_never_a_parent() {
+        for _ in 0..30 {
+            let mut p = Population::new(2, 1, 1, ignore, true);
+            p.threshold = 1000.0;
+            p.config.selection = Box::new(Tournament { size: 1 });
+            p.config.interspecies_rate = 0.0;
+            //only the worst genome has hidden nodes
+            let mut g = p.population[0].clone();
+            for _ in 0..3 {
+                p.random_split(&mut g);
+            }
+            assert_eq!(g.hidden_nodes, 3);
+            p.population[0] = g;
+            let mut rt = RtNeat::new(p, 1, 0);
+            rt.fitness = vec![0.0, 1.0];
+            assert_eq!(rt.step().unwrap(), Some(0));
+            assert!(rt.population.population[0].hidden_nodes <= 1);
+        }
+        //a population of one has no parent for the replacement
+        let mut rt = RtNeat::new(Population::new(1, 1, 1, ignore, true), 1, 0);
+        assert_eq!(rt.step().unwrap(), None);
+        assert_eq!(rt.population.previous_gen.len(), 1);
+    }
+
+    fn duel(u: &Genome, v: &Genome) -> (f64, f64) {
+        let a = u.evaluate(&vec![1.0])[0];
+        let b = v.evaluate(&vec![1.0])[0];
+        if a > b {
+            (1.0, 0.0)
+        } else {
+            (0.0, 1.0)
+        }
+    }
+
+    #[test]
+    fn competitive_coevolution() {
+        let first = Population::new(10, 1, 1, ignore, true);
+        let second = Population::new(12, 1, 1, ignore, true);
+        let mut c = Coevolution::new(first, second, duel);
+        c.hall_size = 3;
+        let (f, s) = c.evaluate();
+        assert_eq!(f.len(), 10);
+        assert_eq!(s.len(), 12);
+        assert!(f.iter().chain(s.iter()).all(|x| (0.0..=1.0).contains(x)));
+        for i in 0..5 {
+            let (f, s) = c.next_generation();
+            assert_eq!(f.len(), 10);
+            assert_eq!(s.len(), 12);
+            assert_eq!(c.first_hall.len(), usize::min(i + 1, 3));
+            assert_eq!(c.second_hall.len(), usize::min(i + 1, 3));
+        }
+        assert_eq!(c.first.gen, 5);
+        assert_eq!(c.second.gen, 5);
+
+        //the second player always scores negative so neither side may advance
+        fn losing(_u: &Genome, _v: &Genome) -> (f64, f64) {
+            (1.0, -1.0)
+        }
+        c.play = losing;
+        c.second.config.fitness_policy = FitnessPolicy::Reject;
+        let hall: Vec<String> = c.first_hall.iter().map(|g| g.to_text()).collect();
+        assert!(matches!(
+            c.try_next_generation(),
+            Err(NeatError::InvalidFitness { .. })
+        ));
+        assert_eq!((c.first.gen, c.second.gen), (5, 5));
+        let after: Vec<String> = c.first_hall.iter().map(|g| g.to_text()).collect();
+        assert_eq!(hall, after);
+    }
+
+    #[test]
+    fn compose_modules() {
+        let mut u = Genome::new(2, 1, ignore);
+        u.add_edge(0, 3, 0, 2.0, true);
+        u.add_edge(1, 3, 1, 1.0, true);
+        u.split_edge(0, 3, 2, 10);
+        let mut v = Genome::new(2, 2, ignore);
+        v.add_edge(1, 3, 0, 3.0, true);
+        v.add_edge(2, 4, 1, 0.5, true);
+        let team = compose(&[&u, &v]).unwrap();
+        assert_eq!(team.output_nodes, 3);
+        assert_eq!(team.hidden_nodes, 1);
+        assert!(team.validate().is_valid());
+        let input = vec![1.0, 2.0];
+        let mut expected = u.evaluate(&input);
+        expected.extend(v.evaluate(&input));
+        assert_eq!(team.evaluate(&input), expected);
+
+        let w = Genome::new(3, 1, ignore);
+        assert!(matches!(
+            compose(&[&u, &w]),
+            Err(NeatError::InvalidGenome(_))
+        ));
+        assert!(compose(&[]).is_err());
+    }
+
+    fn team_fitness(g: &Genome) -> f64 {
+        let out = g.evaluate(&vec![1.0]);
+        1.0 / (1.0 + (out[0] - 1.0).abs() + (out[1] + 1.0).abs())
+    }
+
+    #[test]
+    fn cooperative_coevolution() {
+        let modules = vec![
+            Population::new(10, 1, 1, ignore, true),
+            Population::new(6, 1, 1, ignore, true),
+        ];
+        let mut c = Cooperative::new(modules, team_fitness);
+        c.trials = 3;
+        assert_eq!(c.teams().len(), 30);
+        for _ in 0..3 {
+            let fitness = c.next_generation();
+            assert_eq!(fitness.len(), 2);
+            assert_eq!(fitness[0].len(), 10);
+            assert_eq!(fitness[1].len(), 6);
+        }
+        let (fit, best) = c.best.as_ref().unwrap();
+        assert_eq!(best.output_nodes, 2);
+        assert_eq!(team_fitness(best), *fit);
+
+        //a module that can't advance stops every module from advancing
+        c.modules[1].config.threshold_min = c.modules[1].config.threshold_max + 1.0;
+        let before: Vec<(i32, Vec<String>)> = c
+            .modules
+            .iter()
+            .map(|p| (p.gen, p.population.iter().map(|g| g.to_text()).collect()))
+            .collect();
+        assert!(matches!(
+            c.try_next_generation(),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        for (p, (gen, texts)) in c.modules.iter().zip(&before) {
+            assert_eq!(p.gen, *gen);
+            let now: Vec<String> = p.population.iter().map(|g| g.to_text()).collect();
+            assert_eq!(&now, texts);
+        }
+
+        assert!(Cooperative::try_new(vec![], team_fitness).is_err());
+        let modules = vec![
+            Population::new(4, 1, 1, ignore, true),
+            Population::new(0, 1, 1, ignore, true),
+        ];
+        assert!(matches!(
+            Cooperative::try_new(modules, team_fitness),
+            Err(NeatError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn remap_innovations() {
+        let mut p = Population::new(2, 2, 1, ignore, true);
+        let mut q = Population::new(2, 2, 1, ignore, true);
+        //q has already seen other structures so its numbering differs
+        q.get_inno_split(1, 3);
+        q.get_inno_edge(1, 0);
+        let mut g = p.population[0].clone();
+        p.random_split(&mut g);
+        p.random_split(&mut g);
+        let r = remap(&g, &p, &mut q).unwrap();
+        assert!(r.validate().is_valid());
+        assert_eq!(r.num_connections, g.num_connections);
+        assert_eq!(r.hidden_nodes, g.hidden_nodes);
+        assert_eq!(r.evaluate(&vec![0.5, 2.0]), g.evaluate(&vec![0.5, 2.0]));
+        for e in r.flatten() {
+            assert_eq!(
+                q.inno_edges.get(&(e.from, e.to)),
+                Some(&e.innovation_number)
+            );
+        }
+        //structures known to the receiving tracker keep their numbers
+        let same = remap(&g, &p, &mut q).unwrap();
+        let ids = |g: &Genome| -> Vec<(i32, i32, i32)> {
+            g.flatten()
+                .iter()
+                .map(|e| (e.from, e.to, e.innovation_number))
+                .collect()
+        };
+        assert_eq!(ids(&same), ids(&r));
+    }
+
+    #[test]
+    fn island_migration() {
+        assert_eq!(Topology::Ring.neighbours(2, 3), vec![0]);
+        assert_eq!(Topology::FullyConnected.neighbours(1, 3), vec![0, 2]);
+        let islands: Vec<Population> = (0..3)
+            .map(|_| Population::new(10, 2, 1, ignore, true))
+            .collect();
+        let mut m = Islands::new(islands, Topology::Ring);
+        m.migrants = 1;
+        let mut champion = m.islands[0].population[0].clone();
+        m.islands[0].random_split(&mut champion);
+        m.islands[0].population[4] = champion;
+        let mut fitness: Vec<Vec<f64>> = vec![vec![1.0; 10]; 3];
+        fitness[0][4] = 5.0;
+        m.migrate(&mut fitness).unwrap();
+        assert_eq!(
+            weights(&m.islands[1].population[0]),
+            weights(&m.islands[0].population[4])
+        );
+        assert_eq!(m.islands[1].population[0].hidden_nodes, 1);
+        assert_eq!(fitness[1][0], 5.0);
+
+        for shared in [false, true] {
+            let islands: Vec<Population> = (0..3)
+                .map(|_| Population::new(10, 2, 1, ignore, true))
+                .collect();
+            m = Islands::new(islands, Topology::FullyConnected);
+            m.interval = 2;
+            m.shared_innovations = shared;
+            for _ in 0..6 {
+                let mut fitness: Vec<Vec<f64>> = vec![vec![1.0; 10]; 3];
+                m.next_generation(&mut fitness);
+            }
+            for p in &m.islands {
+                assert_eq!(p.population.len(), 10);
+                for g in &p.population {
+                    assert!(g.validate().is_valid(), "{}", g.validate());
+                }
+            }
+            if shared {
+                //every island numbers its genes with the one tracker
+                let tracker = m.innovations.as_ref().unwrap();
+                for p in &m.islands {
+                    assert!(p.inno_edges.is_empty());
+                    for g in &p.population {
+                        for e in g.flatten() {
+                            assert_eq!(
+                                tracker.inno_edges.get(&(e.from, e.to)),
+                                Some(&e.innovation_number)
+                            );
+                        }
+                    }
+                }
+            } else {
+                assert!(m.innovations.is_none());
+            }
+        }
+        assert!(matches!(
+            m.try_next_generation(&mut vec![vec![1.0; 10]; 2]),
+            Err(NeatError::FitnessLength {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn genome_text_round_trip() {
+        let mut p = Population::new(1, 2, 2, ignore, true);
+        let mut g = p.population[0].clone();
+        p.random_split(&mut g);
+        p.random_edge(&mut g);
+        let r = Genome::from_text(&g.to_text(), ignore).unwrap();
+        assert_eq!(r.to_text(), g.to_text());
+        assert_eq!(r.evaluate(&vec![0.3, -1.2]), g.evaluate(&vec![0.3, -1.2]));
+        assert!(matches!(
+            Genome::from_text("2 1 0 3 0 x 1", ignore),
+            Err(NeatError::Parse(_))
+        ));
+        assert!(matches!(
+            Genome::from_text("2 1 0 3 0", ignore),
+            Err(NeatError::Parse(_))
+        ));
+    }
+
+    fn remote_fitness(g: &Genome) -> f64 {
+        g.evaluate(&vec![1.0, 2.0])[0].abs()
+    }
+
+    #[test]
+    fn distributed_evaluation() {
+        let mut c = Coordinator::bind("127.0.0.1:0").unwrap();
+        c.timeout = Duration::from_millis(200);
+        let addr = c.local_addr().unwrap();
+        for _ in 0..3 {
+            thread::spawn(move || run_worker(addr, ignore, remote_fitness));
+        }
+        //a worker that dies on its first genome and one that never answers
+        thread::spawn(move || {
+            let stream = TcpStream::connect(addr).unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+        });
+        thread::spawn(move || {
+            let stream = TcpStream::connect(addr).unwrap();
+            let mut line = String::new();
+            BufReader::new(stream.try_clone().unwrap())
+                .read_line(&mut line)
+                .unwrap();
+            thread::sleep(Duration::from_secs(2));
+        });
+        c.accept(5).unwrap();
+        let p = Population::new(30, 2, 1, ignore, true);
+        let fitness = c.evaluate(&p.population).unwrap();
+        let expected: Vec<f64> = p.population.iter().map(remote_fitness).collect();
+        assert_eq!(fitness, expected);
+        assert_eq!(c.workers.len(), 3);
+        //surviving workers keep serving later generations
+        let fitness = c.evaluate(&p.population[..5]).unwrap();
+        assert_eq!(fitness, expected[..5].to_vec());
+    }
+
+    #[test]
+    fn distributed_workers_all_dead() {
+        let mut c = Coordinator::bind("127.0.0.1:0").unwrap();
+        let addr = c.local_addr().unwrap();
+        thread::spawn(move || {
+            let stream = TcpStream::connect(addr).unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+        });
+        c.accept(1).unwrap();
+        let p = Population::new(4, 2, 1, ignore, true);
+        assert!(matches!(
+            c.evaluate(&p.population),
+            Err(NeatError::Network(_))
+        ));
+        assert!(c.workers.is_empty());
+        assert!(matches!(
+            c.evaluate(&p.population),
+            Err(NeatError::Network(_))
+        ));
+    }
+
+    #[test]
+    fn single_pole_balancing() {
+        let mut env = CartPole::new();
+        assert_eq!(env.observation()[0], 0.5);
+        //always pushing one way drops the pole
+        let idle = Genome::new(4, 1, ignore);
+        assert!(env.run(&idle, 1000) < 100);
+        assert!(env.failed());
+        //push towards the side the pole is falling to
+        let mut g = Genome::new(4, 1, ignore);
+        g.add_edge(2, 5, 0, 0.41, true);
+        g.add_edge(3, 5, 1, 0.6, true);
+        g.add_edge(4, 5, 2, 0.5 - 0.2094384 - 0.3, true);
+        assert_eq!(env.run(&g, 10_000), 10_000);
+        assert!(!env.failed());
+
+        let p = Population::new(10, 4, 1, sigmoid, true);
+        let fitness = p.evaluate_genomes(single_pole);
+        assert_eq!(fitness.len(), 10);
+        assert!(fitness.iter().all(|f| *f >= 1.0));
+    }
+
+    #[test]
+    fn double_pole_balancing() {
+        let mut env = DoublePole::new(true);
+        assert_eq!(env.observation().len(), 6);
+        assert_eq!(DoublePole::new(false).observation().len(), 3);
+        let idle = Genome::new(6, 1, ignore);
+        assert!(env.run(&idle, 1000) < 100);
+        //linear full state controller
+        let mut g = Genome::new(6, 1, ignore);
+        let k = [-0.49, -0.91, -0.82, -2.6, 0.73, 0.55];
+        for i in 0..6 {
+            g.add_edge(i, 7, i, k[i as usize], true);
+        }
+        g.add_edge(6, 7, 6, 0.5, true);
+        assert_eq!(env.run(&g, 10_000), 10_000);
+        assert!(env.state[2].abs() < 0.01);
+        assert!(double_pole_non_markov(&Genome::new(3, 1, ignore)) < 0.1);
+    }
+
+    struct Ones {
+        steps: i32,
+    }
+
+    impl Environment for Ones {
+        fn reset(&mut self) {
+            self.steps = 0;
+        }
+
+        fn observation(&self) -> Vec<f64> {
+            vec![1.0]
+        }
+
+        fn step(&mut self, action: &[f64]) -> (f64, bool) {
+            self.steps += 1;
+            (action[0], self.steps == 3)
+        }
+    }
+
+    #[test]
+    fn episode_runner() {
+        let mut env = Ones { steps: 0 };
+        let mut runner = EpisodeRunner::new(2, 100);
+        let mut g = Genome::new(1, 1, ignore);
+        g.add_edge(0, 2, 0, 1.0, true);
+        assert_eq!(runner.fitness(&mut env, &g), 3.0);
+        runner.max_steps = 2;
+        assert_eq!(runner.fitness(&mut env, &g), 2.0);
+
+        //previous output fed back makes the network an integrator
+        let mut r = Genome::new(2, 1, ignore);
+        r.add_edge(0, 3, 0, 1.0, true);
+        r.add_edge(1, 3, 1, 1.0, true);
+        runner.max_steps = 100;
+        runner.recurrent = true;
+        assert_eq!(runner.fitness(&mut env, &r), 6.0);
+        let mut controller = Recurrent::new(&r);
+        runner.run(&mut env, &mut controller);
+        assert_eq!(controller.memory, vec![3.0]);
+
+        let p = Population::new(5, 1, 1, ignore, true);
+        runner.recurrent = false;
+        assert_eq!(runner.evaluate_population(&mut env, &p).len(), 5);
+    }
+
+    #[test]
+    fn dataset_parsing() {
+        let text = "a,b,target\n# comment\n0,0,0\n0, 1, 1\n\n1,0,1\n1,1,0\n";
+        let d = Dataset::parse(text, 1).unwrap();
+        assert_eq!(d.len(), 4);
+        assert_eq!(d.inputs[1], vec![0.0, 1.0]);
+        assert_eq!(d.targets[2], vec![1.0]);
+        assert!(matches!(
+            Dataset::parse("1,2\n1,2,3", 1),
+            Err(NeatError::Parse(_))
+        ));
+        assert!(matches!(
+            Dataset::parse("1,2\n1,x", 1),
+            Err(NeatError::Parse(_))
+        ));
+        assert!(matches!(Dataset::parse("1,2", 2), Err(NeatError::Parse(_))));
+        //only the first line that isn't blank or a comment can be a header
+        let commented = Dataset::parse("# x,y\n\nx,y\n1,2", 1).unwrap();
+        assert_eq!(commented.inputs, vec![vec![1.0]]);
+        assert!(matches!(
+            Dataset::parse("# x,y\nx,y\nx,y\n1,2", 1),
+            Err(NeatError::Parse(_))
+        ));
+        assert!(matches!(
+            Dataset::parse("1,2\nx,y\n1,2", 1),
+            Err(NeatError::Parse(_))
+        ));
+        assert_eq!(
+            Dataset::try_new(vec![vec![1.0]; 2], vec![vec![1.0]]).err(),
+            Some(NeatError::TargetLength {
+                expected: 2,
+                found: 1
+            })
+        );
+
+        let path = std::env::temp_dir().join("neat_dataset_parsing.csv");
+        std::fs::write(&path, text).unwrap();
+        let loaded = Dataset::load(path.to_str().unwrap(), 1).unwrap();
+        assert_eq!(loaded.targets, d.targets);
+        assert!(matches!(
+            Dataset::load("/nonexistent/data.csv", 1),
+            Err(NeatError::Io(_))
+        ));
+
+        let (train, validation) = d.split(0.25);
+        assert_eq!(train.len(), 3);
+        assert_eq!(validation.len(), 1);
+        for fraction in [-0.1, 1.5, f64::NAN] {
+            assert!(matches!(
+                d.try_split(fraction),
+                Err(NeatError::InvalidConfig(_))
+            ));
+            assert!(matches!(
+                Supervised::try_new(&d, fraction, Loss::MeanSquared),
+                Err(NeatError::InvalidConfig(_))
+            ));
+        }
+        assert_eq!(d.try_split(1.0).unwrap().1.len(), 4);
+    }
+
+    #[test]
+    fn loss_functions() {
+        assert_eq!(Loss::MeanSquared.error(&[1.0, 0.0], &[0.0, 0.0]), 0.5);
+        assert_eq!(Loss::MeanAbsolute.error(&[0.5, -1.0], &[0.0, 0.0]), 0.75);
+        assert!((Loss::CrossEntropy.error(&[0.5], &[1.0]) - f64::ln(2.0)).abs() < 1e-9);
+        assert!((Loss::CrossEntropy.error(&[0.0, 0.0], &[0.0, 1.0]) - f64::ln(2.0)).abs() < 1e-9);
+        assert!(Loss::CrossEntropy.error(&[0.0], &[1.0]).is_finite());
+        assert_eq!(Loss::Accuracy.error(&[0.7], &[1.0]), 1.0);
+        assert_eq!(
+            Loss::Accuracy.error(&[0.1, 0.3, 0.2], &[0.0, 0.0, 1.0]),
+            0.0
+        );
+        assert_eq!(
+            Loss::MeanSquared.try_error(&[1.0], &[0.0, 0.0]),
+            Err(NeatError::TargetLength {
+                expected: 1,
+                found: 2
+            })
+        );
+        assert_eq!(Loss::MeanSquared.fitness(1.0), 0.5);
+        assert_eq!(Loss::Accuracy.fitness(0.75), 0.75);
+
+        //identity network is perfect on y = x
+        let rows: Vec<Vec<f64>> = (0..10).map(|i| vec![i as f64 / 10.0]).collect();
+        let d = Dataset::new(rows.clone(), rows);
+        let task = Supervised::new(&d, 0.2, Loss::MeanSquared);
+        assert_eq!(task.validation.len(), 2);
+        let mut g = Genome::new(1, 1, ignore);
+        g.add_edge(0, 2, 0, 1.0, true);
+        assert_eq!(task.fitness(&g), 1.0);
+        let mut p = Population::new(3, 1, 1, ignore, false);
+        p.population[1] = g;
+        let fitness = task.evaluate_population(&p);
+        assert_eq!(task.champion(&p, &fitness), (1, 0.0));
+    }
+
+    #[test]
+    fn benchmark_suite() {
+        let p = parity(3);
+        assert_eq!(p.task.train.len(), 8);
+        assert_eq!(p.task.train.targets[7], vec![1.0]);
+        let m = multiplexer(2);
+        assert_eq!((m.inputs(), m.task.train.len()), (6, 64));
+        //address 2 selects the third data bit
+        let row = m
+            .task
+            .train
+            .inputs
+            .iter()
+            .position(|r| *r == vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(m.task.train.targets[row.unwrap()], vec![1.0]);
+        assert_eq!(multiplexer(3).inputs(), 11);
+        assert_eq!(two_spirals().task.train.len(), 194);
+        let i = iris();
+        assert_eq!((i.inputs(), i.outputs(), i.task.train.len()), (4, 3, 30));
+        for name in [
+            "xor",
+            "parity4",
+            "multiplexer11",
+            "sine",
+            "polynomial",
+            "iris",
+        ] {
+            assert_eq!(by_name(name).unwrap().name, name);
+        }
+        assert!(by_name("maze").is_none());
+        assert!(matches!(try_sine(1), Err(NeatError::InvalidConfig(_))));
+        assert!(try_polynomial(0).is_err());
+        assert_eq!(try_polynomial(2).unwrap().task.train.len(), 2);
+
+        //OR and AND hidden units combined into XOR
+        let b = xor();
+        let mut g = Genome::new(2, 1, sigmoid);
+        let or = g.add_node(10);
+        let and = g.add_node(11);
+        g.add_edge(0, or, 0, 10.0, true);
+        g.add_edge(1, or, 1, 10.0, true);
+        g.add_edge(2, or, 2, -5.0, true);
+        g.add_edge(0, and, 3, 10.0, true);
+        g.add_edge(1, and, 4, 10.0, true);
+        g.add_edge(2, and, 5, -15.0, true);
+        g.add_edge(or, 3, 6, 10.0, true);
+        g.add_edge(and, 3, 7, -20.0, true);
+        g.add_edge(2, 3, 8, -5.0, true);
+        assert!(b.solved(&g));
+        assert!(!b.solved(&Genome::new(2, 1, sigmoid)));
+        let pop = b.population(4, sigmoid);
+        let fitness = b.evaluate_population(&pop);
+        assert!(fitness.iter().all(|f| *f < b.fitness(&g)));
+    }
+
+    #[test]
+    fn harness_runs() {
+        let task = xor();
+        let mut h = Harness::new(3, 8, 30, sigmoid);
+        h.seed = 7;
+        let a = h.run_once(&task, 3).unwrap();
+        let b = h.run_once(&task, 3).unwrap();
+        assert_eq!(
+            (a.generations, a.evaluations, a.nodes, a.best_fitness),
+            (b.generations, b.evaluations, b.nodes, b.best_fitness)
+        );
+        assert_eq!(a.evaluations, 30 * (a.generations as i64 + 1));
+
+        let summary = h.run(&task).unwrap();
+        assert_eq!(summary.runs.len(), 3);
+        assert_eq!(summary.runs[2].seed, 9);
+        let failed = summary.runs.iter().filter(|r| !r.solved).count();
+        assert_eq!(summary.failure_rate(), failed as f64 / 3.0);
+        assert!(summary.report().contains("failure rate"));
+        assert_eq!(summary.csv().lines().count(), 4);
+
+        let dir = std::env::temp_dir().join("neat_harness_runs");
+        summary.write(dir.to_str().unwrap()).unwrap();
+        assert!(dir.join("runs.csv").exists());
+        assert!(dir.join("summary.txt").exists());
+    }
+
+    #[test]
+    fn config_from_text() {
+        let e = Experiment::parse(
+            "population = 40 # small\nactivation = tanh\nselection = tournament:4\nfitness_policy = shift\ntarget_species = 8\n",
+        )
+        .unwrap();
+        assert_eq!(e.population, 40);
+        assert_eq!(e.activation, "tanh");
+        assert_eq!(e.config.target_species, Some(8));
+        assert!(matches!(e.config.fitness_policy, FitnessPolicy::Shift));
+        let mut c = Config::default();
+        c.set("fitness_policy", "clamp:0.5").unwrap();
+        assert!(matches!(c.fitness_policy, FitnessPolicy::Clamp(x) if x == 0.5));
+        assert!(matches!(
+            c.set("fitness_policy", "clamp:-1"),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        assert!(matches!(c.set("elitism", "x"), Err(NeatError::Parse(_))));
+        for key in [
+            "elitism",
+            "elitism_min_size",
+            "global_elitism",
+            "min_offspring",
+        ] {
+            assert!(matches!(c.set(key, "-1"), Err(NeatError::InvalidConfig(_))));
+        }
+        assert_eq!(c.elitism, 1);
+        assert!(matches!(c.set("colour", "1"), Err(NeatError::Parse(_))));
+        assert!(matches!(
+            c.set("selection", "best"),
+            Err(NeatError::Parse(_))
+        ));
+        assert!(Experiment::parse("activation = relu").is_err());
+        assert!(matches!(
+            Experiment::parse("threshold_min = 5\nthreshold_max = 2"),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Experiment::parse("threshold_max = NaN"),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        assert!(Experiment::parse("threshold_max = 20\nthreshold_min = 12").is_ok());
+
+        let args: Vec<String> = ["run", "xor", "--seed", "3", "--threads", "2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let o = Options::parse(&args).unwrap();
+        assert_eq!(o.positional, vec!["run", "xor"]);
+        assert_eq!(o.flag("seed", 0).unwrap(), 3);
+        assert_eq!(o.flag("generations", 100).unwrap(), 100);
+        assert!(o.arg(2).is_err());
+        assert!(o.check_flags(&["seed", "threads"]).is_ok());
+        assert!(matches!(o.check_flags(&["seed"]), Err(NeatError::Parse(_))));
+        assert!(Options::parse(&["--seed".to_string()]).is_err());
+    }
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let mut p = Population::new(12, 2, 1, sigmoid, true);
+        for _ in 0..3 {
+            let mut fitness = xor().evaluate_population(&p);
+            p.next_generation(&mut fitness);
+        }
+        let q = Population::from_checkpoint(&p.checkpoint(), sigmoid, Config::default()).unwrap();
+        //species are rebuilt with fresh ids on load
+        let records = |p: &Population| -> Vec<String> {
+            p.checkpoint()
+                .lines()
+                .skip(1)
+                .map(|l| l.to_string())
+                .collect()
+        };
+        assert_eq!(records(&q), records(&p));
+        assert!(q.next_species_id >= p.next_species_id);
+        assert_eq!(
+            (q.gen, q.threshold, q.best_fitness),
+            (p.gen, p.threshold, p.best_fitness)
+        );
+        assert_eq!(q.inno_edges, p.inno_edges);
+        assert!(!q.previous_gen.is_empty());
+        assert!(Population::from_checkpoint("genome 2 1", sigmoid, Config::default()).is_err());
+
+        let dot = p.population[0].to_dot();
+        assert!(dot.starts_with("digraph"));
+        assert_eq!(dot.matches("->").count(), p.population[0].flatten().len());
+
+        let task = xor();
+        assert_eq!(
+            evaluate_parallel(&task, &p, 4).unwrap(),
+            task.evaluate_population(&p)
+        );
+        //a genome the task can't evaluate is an error instead of a crash
+        let q = Population::new(4, 3, 1, sigmoid, true);
+        assert!(matches!(
+            evaluate_parallel(&task, &q, 2),
+            Err(NeatError::InputLength {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn termination_criteria() {
+        let mut p = Population::new(10, 2, 1, sigmoid, true);
+        let outcome = p.run(
+            |pop| vec![1.0; pop.population.len()],
+            &Termination::new().max_generations(3),
+        );
+        assert_eq!(outcome.reason, StopReason::MaxGenerations);
+        assert_eq!((outcome.generations, outcome.evaluations), (3, 30));
+        assert_eq!(p.gen, 2);
+
+        //fitness grows every generation until the threshold
+        let mut p = Population::new(10, 2, 1, sigmoid, true);
+        let criteria = Termination::new()
+            .fitness_threshold(4.0)
+            .max_generations(100);
+        let outcome = p.run(|pop| vec![pop.gen as f64 + 1.0; 10], &criteria);
+        assert_eq!(outcome.reason, StopReason::FitnessThreshold);
+        assert_eq!((outcome.best_fitness, outcome.generations), (4.0, 4));
+
+        let mut p = Population::new(10, 2, 1, sigmoid, true);
+        let criteria = Termination::new().max_evaluations(25).no_improvement(5);
+        assert_eq!(p.run(|_| vec![1.0; 10], &criteria).evaluations, 30);
+        let criteria = Termination::new().no_improvement(5);
+        let outcome = p.run(|_| vec![1.0; 10], &criteria);
+        assert_eq!(
+            (outcome.reason, outcome.generations),
+            (StopReason::NoImprovement, 6)
+        );
+
+        let criteria = Termination::new()
+            .custom("five", |progress| {
+                progress.generation_best.is_some() && progress.generations == 5
+            })
+            .wall_clock(Duration::from_secs(60));
+        let outcome = p.run(|_| vec![2.0; 10], &criteria);
+        assert_eq!(outcome.reason, StopReason::Custom("five".to_string()));
+        let criteria = Termination::new().wall_clock(Duration::ZERO);
+        assert_eq!(
+            p.run(|_| vec![2.0; 10], &criteria).reason,
+            StopReason::WallClock
+        );
+
+        let mut p = Population::new(10, 2, 1, sigmoid, true);
+        assert!(matches!(
+            p.try_run(|_| vec![1.0; 3], &Termination::new().max_generations(2)),
+            Err(NeatError::FitnessLength {
+                expected: 10,
+                found: 3
+            })
+        ));
+        assert!(matches!(
+            p.try_run(|_| vec![1.0; 10], &Termination::new()),
+            Err(NeatError::InvalidConfig(_))
+        ));
+        assert_eq!(p.gen, 0);
+
+        //the generation best is the fittest genome of the current generation only
+        let mut p = Population::new(10, 2, 1, sigmoid, true);
+        let fittest: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
+        let expected = fittest.clone();
+        let criteria = Termination::new().custom("current", move |progress| {
+            let best = progress.generation_best.as_ref().unwrap();
+            assert_eq!(best.to_text(), *expected.borrow());
+            progress.generations == 3
+        });
+        let outcome = p.run(
+            |pop| {
+                //the first generation is the best there will ever be
+                let mut fitness: Vec<f64> = (0..10).map(|i| i as f64).collect();
+                if pop.gen == 0 {
+                    fitness[3] = 100.0;
+                }
+                let best = if pop.gen == 0 { 3 } else { 9 };
+                *fittest.borrow_mut() = pop.population[best].to_text();
+                fitness
+            },
+            &criteria,
+        );
+        assert_eq!(outcome.reason, StopReason::Custom("current".to_string()));
+        assert_eq!(outcome.best_fitness, 100.0);
+    }
+
+    #[test]
+    fn failed_generation_leaves_population_untouched() {
+        let mut p = Population::new(30, 2, 1, ignore, true);
+        p.config.fitness_policy = FitnessPolicy::Reject;
+        let mut fitness: Vec<f64> = vec![1.0; 30];
+        fitness[3] = -1.0;
+        assert!(p.try_next_generation(&mut fitness).is_err());
+        assert_eq!(fitness[3], -1.0);
+        assert_eq!((p.gen, p.stats.len(), p.num_species()), (0, 0, 0));
+    }
 }
@@ -0,0 +1,64 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+const NEAT: &str = env!("CARGO_BIN_EXE_neat");
+
+fn neat(args: &[&str], dir: &Path) -> Output {
+    Command::new(NEAT)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn resume_is_reproducible() {
+    let dir = std::env::temp_dir().join(format!("neat-resume-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let run = neat(&["run", "xor", "--seed", "5", "--generations", "3"], &dir);
+    assert!(run.status.success());
+    let checkpoint = std::fs::read_to_string(dir.join("output/checkpoint.txt")).unwrap();
+    assert!(checkpoint.starts_with("task xor\nactivation sigmoid\nseed 5\n"));
+    std::fs::copy(dir.join("output/checkpoint.txt"), dir.join("start.txt")).unwrap();
+    let resume = || {
+        let out = neat(&["resume", "start.txt", "--generations", "3"], &dir);
+        assert!(out.status.success());
+        out.stdout
+    };
+    assert_eq!(resume(), resume());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn evaluate_checks_columns() {
+    let dir = std::env::temp_dir().join(format!("neat-evaluate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    //a genome with 2 inputs and its first input connected to the output
+    std::fs::write(dir.join("genome.txt"), "2 1 0 3 0 1 1").unwrap();
+    std::fs::write(dir.join("good.csv"), "x,y,target\n1,0,1\n0,1,0\n").unwrap();
+    std::fs::write(dir.join("wide.csv"), "1,0,0,1\n0,1,0,0\n").unwrap();
+    let good = neat(
+        &["evaluate", "genome.txt", "good.csv", "--loss", "mae"],
+        &dir,
+    );
+    assert!(good.status.success());
+    let wide = neat(&["evaluate", "genome.txt", "wide.csv"], &dir);
+    assert!(!wide.status.success());
+    assert!(String::from_utf8_lossy(&wide.stderr).contains("expected 2 inputs but found 3"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unknown_flags_are_rejected() {
+    let dir = std::env::temp_dir().join(format!("neat-flags-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let typo = neat(&["run", "xor", "--generation", "3"], &dir);
+    assert!(!typo.status.success());
+    assert!(String::from_utf8_lossy(&typo.stderr).contains("unknown flag --generation"));
+    //flags of other subcommands aren't accepted either
+    let misplaced = neat(&["dot", "genome.txt", "--seed", "1"], &dir);
+    assert!(!misplaced.status.success());
+    assert!(String::from_utf8_lossy(&misplaced.stderr).contains("unknown flag --seed"));
+    assert!(!dir.join("output").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,54 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+
+const NEAT: &str = env!("CARGO_BIN_EXE_neat");
+
+#[test]
+fn worker_processes() {
+    //a coordinator and two worker processes evolve xor together
+    let output = std::env::temp_dir().join(format!("neat-workers-{}", std::process::id()));
+    let mut coordinator = Command::new(NEAT)
+        .args(["run", "xor", "--listen", "127.0.0.1:0", "--workers", "2"])
+        .args(["--generations", "3", "--seed", "7", "--output"])
+        .arg(&output)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(coordinator.stdout.take().unwrap()).lines();
+    let addr = loop {
+        let line = lines.next().unwrap().unwrap();
+        if let Some(addr) = line.strip_prefix("listening on ") {
+            break addr.to_string();
+        }
+    };
+    let mut workers: Vec<_> = (0..2)
+        .map(|_| {
+            Command::new(NEAT)
+                .args(["worker", &addr, "xor"])
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    let generations = lines
+        .map(|l| l.unwrap())
+        .filter(|l| l.starts_with("generation "))
+        .count();
+    assert!(coordinator.wait().unwrap().success());
+    assert!((1..=4).contains(&generations));
+    //workers exit once the coordinator hangs up
+    for w in &mut workers {
+        assert!(w.wait().unwrap().success());
+    }
+    assert!(output.join("champion.txt").exists());
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[test]
+fn worker_without_coordinator() {
+    let status = Command::new(NEAT)
+        .args(["worker", "127.0.0.1:1", "xor"])
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}